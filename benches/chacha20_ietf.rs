@@ -0,0 +1,23 @@
+use bencher::{ Bencher, benchmark_group, benchmark_main };
+use crypto_api_chachapoly::ChaCha20Ietf;
+
+
+/// Benchmarks `ChaCha20Ietf::xor` over `len` bytes
+fn xor(b: &mut Bencher, len: usize) {
+    let (key, nonce, mut data) = ([0x17; 32], [0x2a; 12], vec![0; len]);
+    b.bytes = len as u64;
+    b.iter(|| ChaCha20Ietf::xor(&key, &nonce, 0, &mut data));
+}
+fn xor_64(b: &mut Bencher) {
+    xor(b, 64)
+}
+fn xor_1k(b: &mut Bencher) {
+    xor(b, 1024)
+}
+fn xor_64k(b: &mut Bencher) {
+    xor(b, 64 * 1024)
+}
+
+
+benchmark_group!(benches, xor_64, xor_1k, xor_64k);
+benchmark_main!(benches);
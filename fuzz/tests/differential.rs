@@ -0,0 +1,164 @@
+use crypto_api_chachapoly::{ ChachaPoly, ChachaPolyIetf, XChachaPoly, crypto_api::cipher::AeadCipher };
+use sodiumoxide::crypto::{
+    stream::salsa20,
+    aead::{ chacha20poly1305, chacha20poly1305_ietf, xchacha20poly1305_ietf }
+};
+
+
+/// The fixed seeds for the deterministic RNG
+const SEEDS: [u8; 8] = [0x00, 0x01, 0x02, 0x03, 0x5a, 0xa5, 0xfe, 0xff];
+/// The amount of test vectors generated per seed
+const ROUNDS: usize = 128;
+/// The exclusive upper bound for the randomly generated plaintext and AD lengths
+const LIMIT: usize = 264;
+
+
+/// A deterministic RNG based on a fixed seed so that any failure is reproducible
+struct SeededRng {
+    seed: salsa20::Key,
+    ctr: u64
+}
+impl SeededRng {
+    /// Creates a new RNG from `seed`
+    pub fn new(seed: u8) -> Self {
+        Self{ seed: salsa20::Key([seed; 32]), ctr: 0 }
+    }
+    
+    /// Creates a `len`-sized vector filled with pseudo random bytes
+    pub fn random_vec(&mut self, len: usize) -> Vec<u8> {
+        // Create nonce
+        let nonce = salsa20::Nonce::from_slice(&self.ctr.to_be_bytes()).unwrap();
+        self.ctr += 1;
+        
+        // Create random bytes
+        let mut buf = vec![0; len];
+        salsa20::stream_xor_inplace(&mut buf, &nonce, &self.seed);
+        buf
+    }
+    /// Creates a vector with a pseudo random length in `0..limit` filled with pseudo random bytes
+    pub fn random_len_vec(&mut self, limit: usize) -> Vec<u8> {
+        let mut len = [0; 8];
+        len.copy_from_slice(&self.random_vec(8));
+        self.random_vec(u64::from_le_bytes(len) as usize % limit)
+    }
+}
+
+
+/// Converts an AD to the `Option` expected by `sodiumoxide`
+fn sodium_ad(ad: &[u8]) -> Option<&[u8]> {
+    match ad.is_empty() {
+        true => None,
+        false => Some(ad)
+    }
+}
+
+
+#[test]
+fn test_chachapoly() {
+    sodiumoxide::init().unwrap();
+    for &seed in SEEDS.iter() {
+        let mut rng = SeededRng::new(seed);
+        for round in 0..ROUNDS {
+            // Create the test vector
+            let (key, nonce) = (rng.random_vec(32), rng.random_vec(8));
+            let (plaintext, ad) = (rng.random_len_vec(LIMIT), rng.random_len_vec(LIMIT));
+            
+            // Seal the data using `crypto_api_chachapoly` and `sodiumoxide`
+            let mut ct_ours = vec![0u8; plaintext.len() + 16];
+            ChachaPoly.seal_to(&mut ct_ours, &plaintext, &ad, &key, &nonce).unwrap();
+            let ct_sodium = chacha20poly1305::seal(
+                &plaintext, sodium_ad(&ad),
+                &chacha20poly1305::Nonce::from_slice(&nonce).unwrap(),
+                &chacha20poly1305::Key::from_slice(&key).unwrap()
+            );
+            assert_eq!(ct_ours, ct_sodium, "Seed: {}, round: {}", seed, round);
+            
+            // Open the libsodium ciphertext
+            let mut pt_ours = vec![0u8; plaintext.len()];
+            ChachaPoly.open_to(&mut pt_ours, &ct_sodium, &ad, &key, &nonce).unwrap();
+            assert_eq!(pt_ours, plaintext, "Seed: {}, round: {}", seed, round);
+        }
+    }
+}
+
+
+#[test]
+fn test_chachapoly_ietf() {
+    sodiumoxide::init().unwrap();
+    for &seed in SEEDS.iter() {
+        let mut rng = SeededRng::new(seed);
+        for round in 0..ROUNDS {
+            // Create the test vector
+            let (key, nonce) = (rng.random_vec(32), rng.random_vec(12));
+            let (plaintext, ad) = (rng.random_len_vec(LIMIT), rng.random_len_vec(LIMIT));
+            
+            // Seal the data using `crypto_api_chachapoly` and `sodiumoxide`
+            let mut ct_ours = vec![0u8; plaintext.len() + 16];
+            ChachaPolyIetf.seal_to(&mut ct_ours, &plaintext, &ad, &key, &nonce).unwrap();
+            let ct_sodium = chacha20poly1305_ietf::seal(
+                &plaintext, sodium_ad(&ad),
+                &chacha20poly1305_ietf::Nonce::from_slice(&nonce).unwrap(),
+                &chacha20poly1305_ietf::Key::from_slice(&key).unwrap()
+            );
+            assert_eq!(ct_ours, ct_sodium, "Seed: {}, round: {}", seed, round);
+            
+            // Open the libsodium ciphertext
+            let mut pt_ours = vec![0u8; plaintext.len()];
+            ChachaPolyIetf.open_to(&mut pt_ours, &ct_sodium, &ad, &key, &nonce).unwrap();
+            assert_eq!(pt_ours, plaintext, "Seed: {}, round: {}", seed, round);
+        }
+    }
+}
+
+
+#[test]
+fn test_xchachapoly() {
+    sodiumoxide::init().unwrap();
+    for &seed in SEEDS.iter() {
+        let mut rng = SeededRng::new(seed);
+        for round in 0..ROUNDS {
+            // Create the test vector
+            let (key, nonce) = (rng.random_vec(32), rng.random_vec(24));
+            let (plaintext, ad) = (rng.random_len_vec(LIMIT), rng.random_len_vec(LIMIT));
+            
+            // Seal the data using `crypto_api_chachapoly` and `sodiumoxide`
+            let mut ct_ours = vec![0u8; plaintext.len() + 16];
+            XChachaPoly.seal_to(&mut ct_ours, &plaintext, &ad, &key, &nonce).unwrap();
+            let ct_sodium = xchacha20poly1305_ietf::seal(
+                &plaintext, sodium_ad(&ad),
+                &xchacha20poly1305_ietf::Nonce::from_slice(&nonce).unwrap(),
+                &xchacha20poly1305_ietf::Key::from_slice(&key).unwrap()
+            );
+            assert_eq!(ct_ours, ct_sodium, "Seed: {}, round: {}", seed, round);
+            
+            // Open the libsodium ciphertext
+            let mut pt_ours = vec![0u8; plaintext.len()];
+            XChachaPoly.open_to(&mut pt_ours, &ct_sodium, &ad, &key, &nonce).unwrap();
+            assert_eq!(pt_ours, plaintext, "Seed: {}, round: {}", seed, round);
+        }
+    }
+}
+
+#[test]
+fn test_large_ad() {
+    sodiumoxide::init().unwrap();
+    let mut rng = SeededRng::new(0x42);
+    for &len in [(1 << 20) - 1, 1 << 20, (4 << 20) + 13, (8 << 20) + 1].iter() {
+        // Create the test vector (large, mostly unaligned AD with an empty plaintext)
+        let (key, nonce, ad) = (rng.random_vec(32), rng.random_vec(12), rng.random_vec(len));
+        
+        // Seal the empty plaintext using `crypto_api_chachapoly` and `sodiumoxide`
+        let mut tag_ours = vec![0u8; 16];
+        ChachaPolyIetf.seal_to(&mut tag_ours, &[], &ad, &key, &nonce).unwrap();
+        let tag_sodium = chacha20poly1305_ietf::seal(
+            &[], Some(&ad),
+            &chacha20poly1305_ietf::Nonce::from_slice(&nonce).unwrap(),
+            &chacha20poly1305_ietf::Key::from_slice(&key).unwrap()
+        );
+        assert_eq!(tag_ours, tag_sodium, "AD length: {}", len);
+        
+        // Open the libsodium tag
+        let mut buf = vec![0u8; 0];
+        ChachaPolyIetf.open_to(&mut buf, &tag_sodium, &ad, &key, &nonce).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,45 @@
+/// A builder for a canonical associated data (AD) byte string composed of multiple fields
+///
+/// Each field is encoded as `le64(field.len()) || field`, so that the field boundaries are
+/// authenticated too; i.e. the fields `"00" | "11"` and `"0" | "011"` result in different AD
+/// bytes (and thus in different tags), while a naive concatenation would be ambiguous.
+///
+/// Example:
+/// ```
+/// use crypto_api_chachapoly::AdBuilder;
+///
+/// let mut ad = AdBuilder::new();
+/// ad.push(b"header").push(b"v1");
+/// assert_eq!(ad.as_bytes().len(), 8 + 6 + 8 + 2);
+/// ```
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct AdBuilder {
+    ad: Vec<u8>
+}
+impl AdBuilder {
+    /// Creates a new, empty AD builder
+    pub fn new() -> Self {
+        Self{ ad: Vec::new() }
+    }
+    
+    /// Appends `field` prefixed with its 8 byte little endian length
+    pub fn push(&mut self, field: &[u8]) -> &mut Self {
+        self.ad.extend_from_slice(&(field.len() as u64).to_le_bytes());
+        self.ad.extend_from_slice(field);
+        self
+    }
+    
+    /// The canonical AD bytes to pass to `seal`/`open`
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.ad
+    }
+    /// Consumes the builder and returns the canonical AD bytes
+    pub fn finish(self) -> Vec<u8> {
+        self.ad
+    }
+}
+impl AsRef<[u8]> for AdBuilder {
+    fn as_ref(&self) -> &[u8] {
+        &self.ad
+    }
+}
\ No newline at end of file
@@ -0,0 +1,166 @@
+use crate::{
+    ChachaPolyError,
+    chacha20_ietf::CHACHA20_KEY,
+    core::chacha20::chacha20_block
+};
+use crypto_api::{
+    cipher::{ CipherInfo, Cipher },
+    rng::{ SecureRng, SecKeyGen }
+};
+use std::{ cmp::min, error::Error };
+
+
+/// The maximum amount of bytes that can be processed by this implementation with one key/nonce
+/// combination
+pub const CHACHA20_ORIG_MAX: usize = usize::MAX;
+
+/// The size of an original ChaCha20 key (256 bits/32 bytes)
+pub const CHACHA20_ORIG_KEY: usize = CHACHA20_KEY;
+/// The size of an original ChaCha20 nonce (64 bits/8 bytes)
+pub const CHACHA20_ORIG_NONCE: usize = 8;
+
+
+/// An implementation of the original [ChaCha20](https://cr.yp.to/chacha.html) with a 64 bit nonce
+/// and a 64 bit block counter
+pub struct ChaCha20;
+impl ChaCha20 {
+    /// Creates a `Cipher` instance with `ChaCha20` as underlying cipher
+    pub fn cipher() -> Box<dyn Cipher> {
+        Box::new(Self)
+    }
+    
+    /// Encrypts `buf` entirely in place with `key` and `nonce`
+    ///
+    /// This is equivalent to `Cipher::encrypt` with `plaintext_len = buf.len()`, but without the
+    /// length parameter (and without the need to box the error).
+    pub fn encrypt_in_place(buf: &mut[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHA20_ORIG_KEY], nonce => [CHACHA20_ORIG_NONCE],
+            buf => [buf, CHACHA20_ORIG_MAX]
+        );
+        
+        // Encrypt the data
+        Self::xor(key, nonce, 0, buf);
+        Ok(())
+    }
+    /// Decrypts `buf` entirely in place with `key` and `nonce`
+    ///
+    /// This is equivalent to `Cipher::decrypt` with `ciphertext_len = buf.len()`.
+    pub fn decrypt_in_place(buf: &mut[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        Self::encrypt_in_place(buf, key, nonce)
+    }
+    
+    /// XORs the bytes in `data` with the ChaCha20 keystream for `key` and `nonce` starting at the
+    /// `n`th block
+    ///
+    /// ## Warning:
+    /// This function panics if
+    ///  - `key` is smaller or larger than 32 bytes/256 bits
+    ///  - `nonce` is smaller or larger than 8 bytes/64 bits
+    ///  - `n` exceeds `2^64 - 1` (which means that `data` must be smaller than `(2^64 - n) * 64`)
+    ///
+    /// __Consider using the `crypto_api`-interface instead of calling this function directly__
+    pub fn xor(key: &[u8], nonce: &[u8], mut n: u64, mut data: &mut[u8]) {
+        // Verify input
+        assert_eq!(CHACHA20_ORIG_KEY, key.len());
+        assert_eq!(CHACHA20_ORIG_NONCE, nonce.len());
+        
+        // XOR `data`
+        let mut buf = vec![0; 64];
+        while !data.is_empty() {
+            // Compute next block
+            chacha20_block(key, nonce, n, &mut buf);
+            n = n.checked_add(1).expect("The ChaCha20 block counter must not exceed 2^64 - 1");
+            
+            // Xor block
+            let to_xor = min(data.len(), buf.len());
+            (0..to_xor).for_each(|i| data[i] = xor!(data[i], buf[i]));
+            data = &mut data[to_xor..];
+        }
+    }
+}
+impl SecKeyGen for ChaCha20 {
+    fn new_sec_key(&self, buf: &mut[u8], rng: &mut dyn SecureRng) -> Result<usize, Box<dyn Error + 'static>> {
+        // Verify input
+        vfy_keygen!(CHACHA20_ORIG_KEY => buf);
+        
+        // Generate key
+        rng.random(&mut buf[..CHACHA20_ORIG_KEY])?;
+        Ok(CHACHA20_ORIG_KEY)
+    }
+}
+impl Cipher for ChaCha20 {
+    fn info(&self) -> CipherInfo {
+        CipherInfo {
+            name: "ChaCha20", is_otc: true,
+            key_len_r: CHACHA20_ORIG_KEY..(CHACHA20_ORIG_KEY + 1),
+            nonce_len_r: CHACHA20_ORIG_NONCE..(CHACHA20_ORIG_NONCE + 1),
+            aead_tag_len_r: 0..1
+        }
+    }
+    
+    fn encrypted_len_max(&self, plaintext_len: usize) -> usize {
+        plaintext_len
+    }
+    
+    fn encrypt(&self, buf: &mut[u8], plaintext_len: usize, key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHA20_ORIG_KEY], nonce => [CHACHA20_ORIG_NONCE],
+            plaintext_len => [buf, CHACHA20_ORIG_MAX]
+        );
+        
+        // Encrypt the data
+        Self::xor(key, nonce, 0, &mut buf[..plaintext_len]);
+        Ok(plaintext_len)
+    }
+    fn encrypt_to(&self, buf: &mut[u8], plaintext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHA20_ORIG_KEY], nonce => [CHACHA20_ORIG_NONCE],
+            plaintext => [buf, CHACHA20_ORIG_MAX]
+        );
+        
+        // Fill `buf` and encrypt the data in place
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+        Self::xor(key, nonce, 0, &mut buf[..plaintext.len()]);
+        Ok(plaintext.len())
+    }
+    
+    fn decrypt(&self, buf: &mut[u8], ciphertext_len: usize, key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_dec!(
+            key => [CHACHA20_ORIG_KEY], nonce => [CHACHA20_ORIG_NONCE],
+            ciphertext_len => [buf, CHACHA20_ORIG_MAX]
+        );
+        
+        // Encrypt the data
+        Self::xor(key, nonce, 0, &mut buf[..ciphertext_len]);
+        Ok(ciphertext_len)
+    }
+    fn decrypt_to(&self, buf: &mut[u8], ciphertext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_dec!(
+            key => [CHACHA20_ORIG_KEY], nonce => [CHACHA20_ORIG_NONCE],
+            ciphertext => [buf, CHACHA20_ORIG_MAX]
+        );
+        
+        // Fill `buf` and encrypt the data in place
+        buf[..ciphertext.len()].copy_from_slice(ciphertext);
+        Self::xor(key, nonce, 0, &mut buf[..ciphertext.len()]);
+        Ok(ciphertext.len())
+    }
+}
\ No newline at end of file
@@ -1,4 +1,7 @@
-use crate::core::chacha20::chacha20_ietf_block;
+use crate::{
+    ChachaPolyError, ChaCha20, Misuse,
+    core::chacha20::{ chacha20_ietf_block, chacha20_ietf_blocks }
+};
 use crypto_api::{
     cipher::{ CipherInfo, Cipher },
     rng::{ SecureRng, SecKeyGen }
@@ -18,6 +21,8 @@ pub const CHACHA20_MAX: usize = usize::max_value(); // 2^32 - 1
 pub const CHACHA20_KEY: usize = 32;
 /// The size of a ChaCha20 nonce (96 bits/12 bytes)
 pub const CHACHA20_NONCE: usize = 12;
+/// The amount of blocks that are generated at once before they are XORed with the data
+const CHACHA20_BATCH: usize = 4;
 
 
 /// An implementation of [ChaCha20 (IETF-version)](https://tools.ietf.org/html/rfc8439)
@@ -28,6 +33,32 @@ impl ChaCha20Ietf {
         Box::new(Self)
     }
     
+    /// Encrypts `buf` entirely in place with `key` and `nonce`
+    ///
+    /// This is equivalent to `Cipher::encrypt` with `plaintext_len = buf.len()`, but without the
+    /// length parameter (and without the need to box the error).
+    pub fn encrypt_in_place(buf: &mut[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHA20_KEY], nonce => [CHACHA20_NONCE],
+            buf => [buf, CHACHA20_MAX]
+        );
+        
+        // Encrypt the data
+        Self::xor(key, nonce, 0, buf);
+        Ok(())
+    }
+    /// Decrypts `buf` entirely in place with `key` and `nonce`
+    ///
+    /// This is equivalent to `Cipher::decrypt` with `ciphertext_len = buf.len()`.
+    pub fn decrypt_in_place(buf: &mut[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        Self::encrypt_in_place(buf, key, nonce)
+    }
+    
     /// XORs the bytes in `data` with the ChaCha20 keystream for `key` and `nonce` starting at the
     /// `n`th block
     ///
@@ -35,27 +66,138 @@ impl ChaCha20Ietf {
     /// This function panics if
     ///  - `key` is smaller or larger than 32 bytes/256 bits
     ///  - `nonce` is smaller or larger than 12 bytes/96 bits
-    ///  - `n` exceeds `2^32 - 1` (which means that `data` must be smaller than `(2^32 - n) * 64`)
+    ///  - the keystream would exceed the last block `2^32 - 1` (which means that `data` must not
+    ///    be larger than `(2^32 - n) * 64` bytes)
     ///
     /// __Consider using the `crypto_api`-interface instead of calling this function directly__
     pub fn xor(key: &[u8], nonce: &[u8], mut n: u32, mut data: &mut[u8]) {
         // Verify input
         assert_eq!(CHACHA20_KEY, key.len());
         assert_eq!(CHACHA20_NONCE, nonce.len());
+        assert!(
+            n as u64 + Self::blocks_for_len(data.len()) <= 4_294_967_296,
+            "The ChaCha20-IETF block counter must not exceed 2^32 - 1"
+        );
         
-        // XOR `data`
-        let mut buf = vec![0; 64];
+        // XOR `data` in batches of up to `CHACHA20_BATCH` blocks
+        let mut buf = vec![0; CHACHA20_BATCH * 64];
         while !data.is_empty() {
-            // Compute next block
-            chacha20_ietf_block(key, nonce, n, &mut buf);
-            n = n.checked_add(1).expect("The ChaCha20-IETF block counter must not exceed 2^32 - 1");
-            
-            // Xor block
+            // Compute the next blocks (but not more than necessary)
             let to_xor = min(data.len(), buf.len());
-            (0..to_xor).for_each(|i| data[i] = xor!(data[i], buf[i]));
+            let blocks = to_xor.div_ceil(64);
+            chacha20_ietf_blocks(key, nonce, n, &mut buf[..blocks * 64]);
+            n = add!(n, blocks as u32);
+            #[cfg(feature = "test-fault-injection")]
+            crate::fault_injection::corrupt_keystream(&mut buf);
+            
+            // Xor the blocks
+            data.iter_mut().zip(&buf[..to_xor]).for_each(|(d, k)| *d = xor!(*d, *k));
             data = &mut data[to_xor..];
         }
     }
+    
+    /// XORs the logical concatenation of all `chunks` with the ChaCha20 keystream for `key` and
+    /// `nonce` starting at the `n`th block (which is equivalent to `xor` over the concatenation)
+    ///
+    /// The keystream continues seamlessly across chunk boundaries, so the chunks don't need to be
+    /// multiples of the block size. This allows scatter-gather encryption without copying the
+    /// chunks into one contiguous buffer.
+    ///
+    /// ## Warning:
+    /// This function panics if
+    ///  - `key` is smaller or larger than 32 bytes/256 bits
+    ///  - `nonce` is smaller or larger than 12 bytes/96 bits
+    ///  - the keystream would exceed the last block `2^32 - 1` (which means that the chunks must
+    ///    not be larger than `(2^32 - n) * 64` bytes in total)
+    ///
+    /// __Consider using the `crypto_api`-interface instead of calling this function directly__
+    pub fn xor_chunks(key: &[u8], nonce: &[u8], mut n: u32, chunks: &mut[&mut[u8]]) {
+        // Verify input
+        assert_eq!(CHACHA20_KEY, key.len());
+        assert_eq!(CHACHA20_NONCE, nonce.len());
+        let len = chunks.iter().fold(0u64, |len, chunk| len + chunk.len() as u64);
+        assert!(
+            n as u64 + len.div_ceil(64) <= 4_294_967_296,
+            "The ChaCha20-IETF block counter must not exceed 2^32 - 1"
+        );
+        
+        // XOR the chunks and carry the position within the current block across chunk boundaries
+        let (mut buf, mut pos) = (vec![0; 64], 64);
+        for chunk in chunks.iter_mut() {
+            let mut data = &mut chunk[..];
+            while !data.is_empty() {
+                // Compute next block if the current block is exhausted
+                if pos == buf.len() {
+                    chacha20_ietf_block(key, nonce, n, &mut buf);
+                    n = add!(n, 1);
+                    pos = 0;
+                }
+                
+                // Xor the remaining bytes of the block
+                let to_xor = min(data.len(), buf.len() - pos);
+                (0..to_xor).for_each(|i| data[i] = xor!(data[i], buf[pos + i]));
+                data = &mut data[to_xor..];
+                pos += to_xor;
+            }
+        }
+    }
+    
+    /// XORs the bytes in `data` with the ChaCha20 keystream for `key` and the 8 byte `nonce` using a
+    /// 64 bit block counter starting at the `n`th block
+    ///
+    /// This extended-counter mode borrows the first nonce word for the upper half of the block
+    /// counter, which lifts the message limit from 256 GiB to `2^64` blocks. __It is not
+    /// compatible with [RFC 8439](https://tools.ietf.org/html/rfc8439)__ – it is the original
+    /// ChaCha20 layout (as used by `ChaCha20` and libsodium's `crypto_stream_chacha20_xor_ic`).
+    ///
+    /// ## Warning:
+    /// This function panics if
+    ///  - `key` is smaller or larger than 32 bytes/256 bits
+    ///  - `n` exceeds `2^64 - 1` (which means that `data` must be smaller than `(2^64 - n) * 64`)
+    ///
+    /// __Consider using the `crypto_api`-interface instead of calling this function directly__
+    pub fn xor_ext(key: &[u8], nonce: &[u8; 8], n: u64, data: &mut[u8]) {
+        ChaCha20::xor(key, nonce, n, data)
+    }
+    
+    /// Computes the amount of 64 byte blocks that are consumed to process `len` bytes (i.e.
+    /// `ceil(len / 64)`)
+    ///
+    /// The last block is `2^32 - 1`, so `xor` (like `keystream`) accepts `data` if and only if
+    /// `n as u64 + blocks_for_len(data.len()) <= 2^32`; you can use this to check your block
+    /// counter beforehand instead of triggering a panic.
+    pub const fn blocks_for_len(len: usize) -> u64 {
+        (len as u64).div_ceil(64)
+    }
+    
+    /// Writes the raw ChaCha20 keystream for `key` and `nonce` starting at the `n`th block into
+    /// `out` (which is equivalent to `xor` over a zero-filled `out`)
+    ///
+    /// Returns `ApiMisuse(TooMuchData)` if the keystream would exceed the last block (`2^32 - 1`)
+    pub fn keystream(key: &[u8], nonce: &[u8], n: u32, out: &mut[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        if key.len() != CHACHA20_KEY {
+            let misuse = Misuse::KeyLen{ expected: CHACHA20_KEY, got: key.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        if nonce.len() != CHACHA20_NONCE {
+            let misuse = Misuse::NonceLen{ expected: CHACHA20_NONCE, got: nonce.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        if n as u64 + Self::blocks_for_len(out.len()) > 4_294_967_296 {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        
+        // Write the keystream blockwise
+        let mut buf = vec![0; 64];
+        for (i, chunk) in out.chunks_mut(64).enumerate() {
+            chacha20_ietf_block(key, nonce, n + i as u32, &mut buf);
+            chunk.copy_from_slice(&buf[..chunk.len()]);
+        }
+        Ok(())
+    }
 }
 impl SecKeyGen for ChaCha20Ietf {
     fn new_sec_key(&self, buf: &mut[u8], rng: &mut dyn SecureRng) -> Result<usize, Box<dyn Error + 'static>> {
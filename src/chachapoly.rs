@@ -0,0 +1,207 @@
+use crate::{
+    ChachaPolyError, ChaCha20, Poly1305,
+    chachapoly_ietf::{ CHACHAPOLY_KEY, CHACHAPOLY_TAG },
+    telemetry::{ count_seal, count_open, count_auth_failure },
+    tag::verify_tag, verify_input::split_tag_mut
+};
+use crypto_api::{
+    cipher::{ CipherInfo, Cipher, AeadCipher },
+    rng::{ SecureRng, SecKeyGen }
+};
+use std::error::Error;
+
+
+/// The maximum amount of bytes that can be processed by this implementation with one key/nonce
+/// combination
+pub const CHACHAPOLY_ORIG_MAX: usize = usize::MAX - CHACHAPOLY_TAG;
+
+/// The size of an original ChachaPoly key (256 bits/32 bytes)
+pub const CHACHAPOLY_ORIG_KEY: usize = CHACHAPOLY_KEY;
+/// The size of an original ChachaPoly nonce (64 bits/8 bytes)
+pub const CHACHAPOLY_ORIG_NONCE: usize = 8;
+/// The size of an original ChachaPoly authentication tag
+pub const CHACHAPOLY_ORIG_TAG: usize = CHACHAPOLY_TAG;
+
+
+/// Encrypts `data` in place and authenticates it with `ad` into `tag` using `key` and `nonce`
+fn chachapoly_orig_seal(data: &mut[u8], tag: &mut[u8], ad: &[u8], key: &[u8], nonce: &[u8]) {
+    // Encrypt the data
+    ChaCha20::xor(key, nonce, 1, data);
+    
+    // Compute the Poly1305 key and the authentication tag
+    let mut pkey = vec![0; 32];
+    ChaCha20::xor(key, nonce, 0, &mut pkey);
+    Poly1305::chachapoly_orig_auth(tag, ad, data, &pkey);
+    count_seal("ChachaPoly");
+}
+/// Validates `data` with `ad` and decrypts it in place using `key` and `nonce`
+fn chachapoly_orig_open(data: &mut[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+    -> Result<(), ChachaPolyError>
+{
+    // Compute the Poly1305 key and the authentication tag
+    let (mut pkey, mut vfy_tag) = (vec![0; 32], vec![0; 16]);
+    ChaCha20::xor(key, nonce, 0, &mut pkey);
+    Poly1305::chachapoly_orig_auth(&mut vfy_tag, ad, data, &pkey);
+    
+    // Validate the recomputed and the original tag
+    match verify_tag(tag, &vfy_tag) {
+        true => {
+            ChaCha20::xor(key, nonce, 1, data);
+            count_open("ChachaPoly");
+            Ok(())
+        },
+        false => {
+            count_auth_failure("ChachaPoly");
+            Err(ChachaPolyError::InvalidData)
+        }
+    }
+}
+/// Copies `data` into `buf`, validates it with `ad` and decrypts it in place using `key` and `nonce`
+///
+/// Only `buf[..data.len()]` is written to; if the validation fails, these bytes are erased again
+fn chachapoly_orig_open_to(buf: &mut[u8], data: &[u8], tag: &[u8], ad: &[u8], key: &[u8],
+    nonce: &[u8]) -> Result<(), ChachaPolyError>
+{
+    // Copy the data into buf and decrypt in place
+    let buf = &mut buf[..data.len()];
+    buf.copy_from_slice(data);
+    match chachapoly_orig_open(buf, tag, ad, key, nonce) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            buf.iter_mut().for_each(|b| *b = 0);
+            Err(e)
+        }
+    }
+}
+
+
+/// An implementation of the original ChachaPoly AEAD-construction (as used before
+/// [RFC 7539](https://tools.ietf.org/html/rfc7539), e.g. by libsodium's
+/// `crypto_aead_chacha20poly1305` or older OpenSSH/TLS drafts)
+///
+/// This construction uses the original ChaCha20 with a 64 bit nonce; the Poly1305 key is derived
+/// from block 0 and the data is encrypted starting at block 1. Unlike ChachaPoly-IETF, the AD and
+/// the ciphertext are not padded before they are authenticated.
+///
+/// ## Output buffers
+/// All `*_to`-functions only write to the first `n` bytes of `buf` where `n` is the output length
+/// (which is returned on success); the remaining bytes of `buf` are never touched. If opening
+/// fails, the first `n` bytes are erased again so that `buf` never contains unauthenticated data.
+pub struct ChachaPoly;
+impl ChachaPoly {
+    /// Creates a `Cipher` instance with `ChachaPoly` as underlying cipher
+    pub fn cipher() -> Box<dyn Cipher> {
+        Box::new(Self)
+    }
+    /// Creates a `AeadCipher` instance with `ChachaPoly` as underlying AEAD cipher
+    pub fn aead_cipher() -> Box<dyn AeadCipher> {
+        Box::new(Self)
+    }
+}
+impl SecKeyGen for ChachaPoly {
+    fn new_sec_key(&self, buf: &mut[u8], rng: &mut dyn SecureRng) -> Result<usize, Box<dyn Error + 'static>> {
+        // Validate input
+        vfy_keygen!(CHACHAPOLY_ORIG_KEY => buf);
+        
+        // Generate key
+        rng.random(&mut buf[..CHACHAPOLY_ORIG_KEY])?;
+        Ok(CHACHAPOLY_ORIG_KEY)
+    }
+}
+impl Cipher for ChachaPoly {
+    fn info(&self) -> CipherInfo {
+        CipherInfo {
+            name: "ChachaPoly", is_otc: true,
+            key_len_r: CHACHAPOLY_ORIG_KEY..(CHACHAPOLY_ORIG_KEY + 1),
+            nonce_len_r: CHACHAPOLY_ORIG_NONCE..(CHACHAPOLY_ORIG_NONCE + 1),
+            aead_tag_len_r: CHACHAPOLY_ORIG_TAG..(CHACHAPOLY_ORIG_TAG + 1)
+        }
+    }
+    
+    fn encrypted_len_max(&self, plaintext_len: usize) -> usize {
+        plaintext_len + 16
+    }
+    
+    fn encrypt(&self, buf: &mut[u8], plaintext_len: usize, key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        self.seal(buf, plaintext_len, &[], key, nonce)
+    }
+    fn encrypt_to(&self, buf: &mut[u8], plaintext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        self.seal_to(buf, plaintext, &[], key, nonce)
+    }
+    
+    fn decrypt(&self, buf: &mut[u8], ciphertext_len: usize, key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        self.open(buf, ciphertext_len, &[], key, nonce)
+    }
+    fn decrypt_to(&self, buf: &mut[u8], ciphertext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        self.open_to(buf, ciphertext, &[], key, nonce)
+    }
+}
+impl AeadCipher for ChachaPoly {
+    fn seal(&self, buf: &mut[u8], plaintext_len: usize, ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_seal!(
+            key => [CHACHAPOLY_ORIG_KEY], nonce => [CHACHAPOLY_ORIG_NONCE],
+            plaintext_len => [buf, CHACHAPOLY_ORIG_MAX]
+        );
+        
+        // Seal the data
+        let (data, tag) = split_tag_mut(buf, plaintext_len, CHACHAPOLY_ORIG_TAG)?;
+        chachapoly_orig_seal(data, tag, ad, key, nonce);
+        Ok(plaintext_len + CHACHAPOLY_ORIG_TAG)
+    }
+    fn seal_to(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_seal!(
+            key => [CHACHAPOLY_ORIG_KEY], nonce => [CHACHAPOLY_ORIG_NONCE],
+            plaintext => [buf, CHACHAPOLY_ORIG_MAX]
+        );
+        
+        // Copy the plaintext into buf and seal in place
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), CHACHAPOLY_ORIG_TAG)?;
+        data.copy_from_slice(plaintext);
+        chachapoly_orig_seal(data, tag, ad, key, nonce);
+        Ok(plaintext.len() + CHACHAPOLY_ORIG_TAG)
+    }
+    
+    fn open(&self, buf: &mut[u8], ciphertext_len: usize, ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_open_in_place!(
+            key => [CHACHAPOLY_ORIG_KEY], nonce => [CHACHAPOLY_ORIG_NONCE],
+            ciphertext_len => [buf, CHACHAPOLY_ORIG_TAG, CHACHAPOLY_ORIG_MAX]
+        );
+        
+        // Open the data
+        let plaintext_len = ciphertext_len - CHACHAPOLY_ORIG_TAG;
+        let (data, tag) = split_tag_mut(buf, plaintext_len, CHACHAPOLY_ORIG_TAG)?;
+        chachapoly_orig_open(data, tag, ad, key, nonce)?;
+        Ok(plaintext_len)
+    }
+    fn open_to(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_ORIG_KEY], nonce => [CHACHAPOLY_ORIG_NONCE],
+            ciphertext => [buf, CHACHAPOLY_ORIG_TAG, CHACHAPOLY_ORIG_MAX]
+        );
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let (data, tag) = ciphertext.split_at(ciphertext.len() - CHACHAPOLY_ORIG_TAG);
+        chachapoly_orig_open_to(buf, data, &tag[..CHACHAPOLY_ORIG_TAG], ad, key, nonce)?;
+        Ok(ciphertext.len() - CHACHAPOLY_ORIG_TAG)
+    }
+}
\ No newline at end of file
@@ -1,9 +1,15 @@
-use crate::{ ChachaPolyError, ChaCha20Ietf, Poly1305 };
+use crate::{
+    ChachaPolyError, ChaCha20Ietf, Poly1305, Tag, Misuse, SecretKey,
+    core::chacha20::{ hchacha20_hash, chacha20_derive_key, chacha20_ietf_block },
+    telemetry::{ count_seal, count_open, count_auth_failure },
+    nonce::{ Endian, nonce_from_counter }, tag::verify_tag, verify_input::split_tag_mut,
+    envelope::{ WrappedCek, CHACHAPOLY_WRAPPED_CEK }
+};
 use crypto_api::{
     cipher::{ CipherInfo, Cipher, AeadCipher },
     rng::{ SecureRng, SecKeyGen }
 };
-use std::error::Error;
+use std::{ convert::TryInto, error::Error, io::Read, ops::Range };
 
 
 /// The maximum amount of bytes that can be processed with one key/nonce combination
@@ -12,6 +18,13 @@ pub const CHACHAPOLY_MAX: usize = (4_294_967_296 - 1) * 64; // (2^32 - 1) * BLOC
 /// The maximum amount of bytes that can be processed with one key/nonce combination
 #[cfg(target_pointer_width = "32")]
 pub const CHACHAPOLY_MAX: usize = usize::max_value() - 16; // 2^32 - 1 - 16
+/// The maximum length of the associated data (its length is encoded as 64 bit integer)
+///
+/// _Note: Since `usize` is at most 64 bits wide, every AD slice satisfies this limit; this is
+/// enforced at compile time, so no AD length check is necessary at runtime._
+pub const CHACHAPOLY_AD_MAX: u64 = u64::MAX;
+// Ensure that every AD length can be encoded in the footer
+const _: () = assert!(usize::MAX as u128 <= CHACHAPOLY_AD_MAX as u128);
 
 /// The size of a ChaChaPoly key (256 bits/32 bytes)
 pub const CHACHAPOLY_KEY: usize = 32;
@@ -20,12 +33,63 @@ pub const CHACHAPOLY_NONCE: usize = 12;
 /// The size of a ChaChaPoly authentication tag
 pub const CHACHAPOLY_TAG: usize = 16;
 
+/// The label to derive the Poly1305 key for the synthetic nonce from the sealing key
+///
+/// _Note: The label replaces the ChaCha20 constants, so the subkey can never coincide with a
+/// ChaCha20 keystream block under the sealing key (which an attacker may observe)._
+const SYNTHETIC_MAC_LABEL: &[u8; 16] = b"chachasiv-mackey";
+/// The label to derive the PRF key for the synthetic nonce from the sealing key
+const SYNTHETIC_PRF_LABEL: &[u8; 16] = b"chachasiv-prfkey";
 
-/// Encrypts `data` in place and authenticates it with `ad` into `tag` using `key` and `nonce`
-pub fn chachapoly_seal(data: &mut[u8], tag: &mut[u8], ad: &[u8], key: &[u8], nonce: &[u8]) {
-    // Encrypt the data
-    ChaCha20Ietf::xor(key, nonce, 1, data);
-    
+/// The nonce used to seal a multi-recipient payload (this is safe because each CEK is used exactly
+/// once)
+const PAYLOAD_NONCE: [u8; CHACHAPOLY_NONCE] = [0; CHACHAPOLY_NONCE];
+
+
+/// Computes the sealed length (ciphertext + tag) for a `plaintext_len`-sized plaintext
+///
+/// This is a `const fn` so it can be used to size buffers at compile time; it applies to
+/// XChachaPoly as well. Example:
+/// ```
+/// use crypto_api_chachapoly::{
+///     ChachaPolyIetf, chachapoly_ct_len, crypto_api::cipher::AeadCipher
+/// };
+///
+/// const PLAINTEXT: &[u8; 5] = b"Hello";
+/// let mut buf = [0; chachapoly_ct_len(PLAINTEXT.len())];
+/// let len = ChachaPolyIetf.seal_to(&mut buf, PLAINTEXT, b"", &[0; 32], &[0; 12]).unwrap();
+/// assert_eq!(len, buf.len());
+/// ```
+pub const fn chachapoly_ct_len(plaintext_len: usize) -> usize {
+    plaintext_len + CHACHAPOLY_TAG
+}
+/// Computes the opened length (plaintext) for a `ciphertext_len`-sized ciphertext (including the
+/// tag)
+///
+/// This is a `const fn` so it can be used to size buffers at compile time; it applies to
+/// XChachaPoly as well. A ciphertext shorter than the tag cannot be opened and yields `0`.
+/// Example:
+/// ```
+/// use crypto_api_chachapoly::{
+///     ChachaPolyIetf, chachapoly_pt_len, crypto_api::cipher::AeadCipher
+/// };
+///
+/// const SEALED_LEN: usize = 21;
+/// let mut sealed = [0; SEALED_LEN];
+/// ChachaPolyIetf.seal_to(&mut sealed, b"Hello", b"", &[0; 32], &[0; 12]).unwrap();
+///
+/// let mut buf = [0; chachapoly_pt_len(SEALED_LEN)];
+/// let len = ChachaPolyIetf.open_to(&mut buf, &sealed, b"", &[0; 32], &[0; 12]).unwrap();
+/// assert_eq!(&buf[..len], b"Hello");
+/// ```
+pub const fn chachapoly_pt_len(ciphertext_len: usize) -> usize {
+    ciphertext_len.saturating_sub(CHACHAPOLY_TAG)
+}
+
+
+/// Computes the authentication tag for the ciphertext `data` with `ad` into `tag` using `key` and
+/// `nonce`
+fn chachapoly_tag(tag: &mut[u8], data: &[u8], ad: &[u8], key: &[u8], nonce: &[u8]) {
     // Create the footer
     let mut foot = Vec::with_capacity(16);
     foot.extend_from_slice(&(ad.len() as u64).to_le_bytes());
@@ -36,30 +100,120 @@ pub fn chachapoly_seal(data: &mut[u8], tag: &mut[u8], ad: &[u8], key: &[u8], non
     ChaCha20Ietf::xor(key, nonce, 0, &mut pkey);
     Poly1305::chachapoly_auth(tag, ad, data, &foot, &pkey);
 }
-/// Validates `data` with `ad` and decrypts it in place using `key` and `nonce`
-pub fn chachapoly_open(data: &mut[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+/// Computes the authentication tag like `chachapoly_tag` but reads the AD from `ad`
+fn chachapoly_tag_reader(tag: &mut[u8], data: &[u8], ad: &mut dyn Read, key: &[u8], nonce: &[u8])
     -> Result<(), Box<dyn Error + 'static>>
 {
-    // Create the footer
+    let mut pkey = vec![0; 32];
+    ChaCha20Ietf::xor(key, nonce, 0, &mut pkey);
+    Ok(Poly1305::chachapoly_auth_reader(tag, ad, data, &pkey)?)
+}
+/// Derives the synthetic nonce for `plaintext` and `ad` into `nonce` using `key`
+///
+/// The nonce is `HChaCha20(prf_key, Poly1305(mac_key, ad, plaintext))[..12]` where both subkeys
+/// are derived from `key` with `chacha20_derive_key` and the Poly1305 input is encoded exactly like
+/// the ChachaPoly-IETF MAC input. The Poly1305 output is never revealed, so the Poly1305 key can be
+/// reused for all messages.
+fn chachapoly_synthetic_nonce(nonce: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8]) {
+    // Derive the subkeys
+    let (mut mac_key, mut prf_key) = (vec![0; 32], vec![0; 32]);
+    chacha20_derive_key(SYNTHETIC_MAC_LABEL, key, &mut mac_key);
+    chacha20_derive_key(SYNTHETIC_PRF_LABEL, key, &mut prf_key);
+    
+    // Hash the AD and the plaintext
     let mut foot = Vec::with_capacity(16);
     foot.extend_from_slice(&(ad.len() as u64).to_le_bytes());
-    foot.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    foot.extend_from_slice(&(plaintext.len() as u64).to_le_bytes());
+    let mut hash = vec![0; CHACHAPOLY_TAG];
+    Poly1305::chachapoly_auth(&mut hash, ad, plaintext, &foot, &mac_key);
     
-    // Compute the Poly1305 key and the authentication tag
-    let (mut pkey, mut vfy_tag) = (vec![0; 32], vec![0; 16]);
-    ChaCha20Ietf::xor(key, nonce, 0, &mut pkey);
-    Poly1305::chachapoly_auth(&mut vfy_tag, ad, data, &foot, &pkey);
+    // Compute the nonce from the hash
+    let mut buf = vec![0; 32];
+    hchacha20_hash(&prf_key, &hash, &mut buf);
+    nonce.copy_from_slice(&buf[..CHACHAPOLY_NONCE]);
+}
+/// Encrypts `data` in place and authenticates it with `ad` into `tag` using `key` and `nonce`
+///
+/// This is the raw ChachaPoly-IETF construction without any buffer layout: `data` is the plaintext
+/// which is replaced by the ciphertext of the same length, and the 16 byte authentication tag is
+/// written to `tag[..16]`. Where you store the tag relative to the ciphertext is up to you.
+///
+/// Both `data` and `ad` may be empty; an empty AD or ciphertext contributes no block to the MAC, so
+/// if both are empty, the tag only authenticates the 16 byte footer (two zero lengths).
+///
+/// ## Warning:
+/// This function panics if
+///  - `tag` is smaller than 16 bytes
+///  - `key` is smaller or larger than 32 bytes/256 bits
+///  - `nonce` is smaller or larger than 12 bytes/96 bits
+///  - `data` is larger than `(2^32 - 1) * 64` bytes
+///
+/// __Consider using the `crypto_api`-interface instead of calling this function directly__
+pub fn chachapoly_seal(data: &mut[u8], tag: &mut[u8], ad: &[u8], key: &[u8], nonce: &[u8]) {
+    // Encrypt and authenticate the data
+    ChaCha20Ietf::xor(key, nonce, 1, data);
+    chachapoly_tag(tag, data, ad, key, nonce);
+    count_seal("ChachaPolyIetf");
+}
+/// Validates `data` with `ad` and decrypts it in place using `key` and `nonce`
+///
+/// This is the raw ChachaPoly-IETF construction without any buffer layout: `data` is the ciphertext
+/// (without tag) and `tag` is the 16 byte authentication tag. `data` is only decrypted if the tag
+/// is valid; otherwise `data` is left untouched and `ChachaPolyError::InvalidData` is returned (this
+/// is also the case if `tag` is not exactly 16 bytes long).
+///
+/// ## Warning:
+/// This function panics if
+///  - `key` is smaller or larger than 32 bytes/256 bits
+///  - `nonce` is smaller or larger than 12 bytes/96 bits
+///  - `data` is larger than `(2^32 - 1) * 64` bytes
+///
+/// __Consider using the `crypto_api`-interface instead of calling this function directly__
+pub fn chachapoly_open(data: &mut[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+    -> Result<(), ChachaPolyError>
+{
+    // Recompute the authentication tag
+    let mut vfy_tag = vec![0; 16];
+    chachapoly_tag(&mut vfy_tag, data, ad, key, nonce);
     
     // Validate the recomputed and the original tag
-    Ok(match eq_ct!(&tag, &vfy_tag) {
-        true => ChaCha20Ietf::xor(key, nonce, 1, data),
-        false => Err(ChachaPolyError::InvalidData)?
+    Ok(match verify_tag(tag, &vfy_tag) {
+        true => {
+            ChaCha20Ietf::xor(key, nonce, 1, data);
+            count_open("ChachaPolyIetf");
+        },
+        false => {
+            count_auth_failure("ChachaPolyIetf");
+            Err(ChachaPolyError::InvalidData)?
+        }
     })
 }
+/// Copies `data` into `buf`, validates it with `ad` and decrypts it in place using `key` and `nonce`
+///
+/// Only `buf[..data.len()]` is written to; if the validation fails, these bytes are erased again
+fn chachapoly_open_to(buf: &mut[u8], data: &[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+    -> Result<(), ChachaPolyError>
+{
+    // Copy the data into buf and decrypt in place
+    let buf = &mut buf[..data.len()];
+    buf.copy_from_slice(data);
+    match chachapoly_open(buf, tag, ad, key, nonce) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            buf.iter_mut().for_each(|b| *b = 0);
+            Err(e)
+        }
+    }
+}
 
 
 /// An implementation of the
 /// [ChachaPoly-IETF AEAD-construction](https://tools.ietf.org/html/rfc8439)
+///
+/// ## Output buffers
+/// All `*_to`-functions only write to the first `n` bytes of `buf` where `n` is the output length
+/// (which is returned on success); the remaining bytes of `buf` are never touched. If opening
+/// fails, the first `n` bytes are erased again so that `buf` never contains unauthenticated data.
 pub struct ChachaPolyIetf;
 impl ChachaPolyIetf {
     /// Creates a `Cipher` instance with `ChachaPolyIetf` as underlying cipher
@@ -70,6 +224,709 @@ impl ChachaPolyIetf {
     pub fn aead_cipher() -> Box<dyn AeadCipher> {
         Box::new(Self)
     }
+    
+    /// Encrypts `plaintext` into `buf` and authenticates it with `ad` into a detached tag
+    ///
+    /// This is the counterpart to `open_detached_to` for formats where the ciphertext and the tag
+    /// are stored in separate buffers; the ciphertext length is always `plaintext.len()`.
+    pub fn seal_detached_to(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8],
+        nonce: &[u8]) -> Result<Tag, ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            plaintext => [buf, CHACHAPOLY_MAX]
+        );
+        
+        // Copy the plaintext into buf and seal in place
+        let (data, mut tag) = (&mut buf[..plaintext.len()], Tag([0; CHACHAPOLY_TAG]));
+        data.copy_from_slice(plaintext);
+        chachapoly_seal(data, &mut tag.0, ad, key, nonce);
+        Ok(tag)
+    }
+    /// Encrypts `plaintext` into `body_out` and authenticates it with `ad` into `tag_out`
+    ///
+    /// This is like `seal_detached_to`, but both outputs are borrowed from the caller's layout (e.g.
+    /// a header and a body carved from the same allocation). `body_out` must be exactly
+    /// `plaintext.len()` bytes long; otherwise an `ApiMisuse` error is returned and neither output
+    /// is modified.
+    pub fn seal_split(&self, body_out: &mut[u8], tag_out: &mut[u8; CHACHAPOLY_TAG],
+        plaintext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8]) -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            plaintext => [body_out, CHACHAPOLY_MAX]
+        );
+        if body_out.len() != plaintext.len() {
+            Err(ChachaPolyError::ApiMisuse(Misuse::BufferLen))?
+        }
+        
+        // Copy the plaintext into the body and seal in place
+        body_out.copy_from_slice(plaintext);
+        chachapoly_seal(body_out, tag_out, ad, key, nonce);
+        Ok(())
+    }
+    /// Encrypts `plaintext` into `buf` and authenticates it with the AD read from `ad`
+    ///
+    /// This is equivalent to `seal_to` with the AD read fully into memory, but the AD is consumed
+    /// in chunks so that e.g. a large on-disk header can be authenticated without loading it. The
+    /// AD is read until EOF; any I/O error is returned as is. Returns the sealed length.
+    pub fn seal_to_with_ad_reader(&self, buf: &mut[u8], plaintext: &[u8], ad: &mut dyn Read,
+        key: &[u8], nonce: &[u8]) -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_seal!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            plaintext => [buf, CHACHAPOLY_MAX]
+        );
+        
+        // Copy the plaintext into buf, encrypt it in place and authenticate it
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), CHACHAPOLY_TAG)?;
+        data.copy_from_slice(plaintext);
+        ChaCha20Ietf::xor(key, nonce, 1, data);
+        chachapoly_tag_reader(tag, data, ad, key, nonce)?;
+        count_seal("ChachaPolyIetf");
+        Ok(plaintext.len() + CHACHAPOLY_TAG)
+    }
+    /// Validates `ciphertext` with the AD read from `ad` and decrypts it into `buf`
+    ///
+    /// This is the counterpart to `seal_to_with_ad_reader`; it is equivalent to `open_to` with the
+    /// AD read fully into memory. Returns the plaintext length.
+    pub fn open_to_with_ad_reader(&self, buf: &mut[u8], ciphertext: &[u8], ad: &mut dyn Read,
+        key: &[u8], nonce: &[u8]) -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Validate the ciphertext before it is copied into buf
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        let mut vfy_tag = vec![0; CHACHAPOLY_TAG];
+        chachapoly_tag_reader(&mut vfy_tag, data, ad, key, nonce)?;
+        if !verify_tag(tag, &vfy_tag) {
+            count_auth_failure("ChachaPolyIetf");
+            Err(ChachaPolyError::InvalidData)?
+        }
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let buf = &mut buf[..data.len()];
+        buf.copy_from_slice(data);
+        ChaCha20Ietf::xor(key, nonce, 1, buf);
+        count_open("ChachaPolyIetf");
+        Ok(data.len())
+    }
+    /// Encrypts `plaintext` with `enc_key` and authenticates the ciphertext and `ad` with `mac_key`
+    /// into `buf` (encrypt-then-MAC with independent keys); the layout is `ciphertext || tag`
+    ///
+    /// The data is encrypted with ChaCha20-IETF under `enc_key` starting at block 1, and the
+    /// one-time Poly1305 key is derived from block 0 of the ChaCha20-IETF keystream under `mac_key`
+    /// (so `mac_key` can safely be used for many messages as long as the nonces are unique). The
+    /// MAC input is encoded exactly like in ChachaPoly-IETF. Returns the sealed length.
+    ///
+    /// ## Warning:
+    /// This is __NOT__ the [RFC 8439](https://tools.ietf.org/html/rfc8439) AEAD construction but an
+    /// encrypt-then-MAC variant of it; the output can only be opened with `open_etm`. (If
+    /// `enc_key == mac_key`, it degenerates to the regular ChachaPoly-IETF.)
+    pub fn seal_etm(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], enc_key: &[u8],
+        mac_key: &[u8], nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_seal!(
+            enc_key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            plaintext => [buf, CHACHAPOLY_MAX]
+        );
+        if mac_key.len() != CHACHAPOLY_KEY {
+            let misuse = Misuse::KeyLen{ expected: CHACHAPOLY_KEY, got: mac_key.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Copy the plaintext into buf, encrypt it in place and authenticate it
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), CHACHAPOLY_TAG)?;
+        data.copy_from_slice(plaintext);
+        ChaCha20Ietf::xor(enc_key, nonce, 1, data);
+        chachapoly_tag(tag, data, ad, mac_key, nonce);
+        count_seal("ChachaPolyIetf");
+        Ok(plaintext.len() + CHACHAPOLY_TAG)
+    }
+    /// Validates `ciphertext` (`ciphertext || tag`) and `ad` with `mac_key` and decrypts it into
+    /// `buf` with `enc_key`
+    ///
+    /// This is the counterpart to `seal_etm`; the ciphertext is only decrypted if it is valid.
+    /// Returns the plaintext length.
+    pub fn open_etm(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], enc_key: &[u8],
+        mac_key: &[u8], nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_open!(
+            enc_key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        if mac_key.len() != CHACHAPOLY_KEY {
+            let misuse = Misuse::KeyLen{ expected: CHACHAPOLY_KEY, got: mac_key.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Validate the ciphertext before it is copied into buf
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        let mut vfy_tag = vec![0; CHACHAPOLY_TAG];
+        chachapoly_tag(&mut vfy_tag, data, ad, mac_key, nonce);
+        if !verify_tag(tag, &vfy_tag) {
+            count_auth_failure("ChachaPolyIetf");
+            Err(ChachaPolyError::InvalidData)?
+        }
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let buf = &mut buf[..data.len()];
+        buf.copy_from_slice(data);
+        ChaCha20Ietf::xor(enc_key, nonce, 1, buf);
+        count_open("ChachaPolyIetf");
+        Ok(data.len())
+    }
+    /// Parses the framed `input` (`magic || nonce || ciphertext || tag`), validates it with `ad` and
+    /// returns the decrypted plaintext
+    ///
+    /// Returns `ApiMisuse(InvalidMagic)` if `input` does not start with `magic` (compared in
+    /// constant time), `TruncatedInput` if `input` is too short to contain the magic, the 12 byte
+    /// nonce and the tag, and `InvalidData` if the authentication fails.
+    pub fn open_framed_with_nonce(&self, input: &[u8], magic: &[u8], ad: &[u8], key: &[u8])
+        -> Result<Vec<u8>, ChachaPolyError>
+    {
+        // Parse the header
+        if input.len() < magic.len() + CHACHAPOLY_NONCE {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        let (header, ciphertext) = input.split_at(magic.len() + CHACHAPOLY_NONCE);
+        let (input_magic, nonce) = header.split_at(magic.len());
+        if !eq_ct!(input_magic, magic) {
+            Err(ChachaPolyError::ApiMisuse(Misuse::InvalidMagic))?
+        }
+        
+        // Verify input
+        let mut buf = vec![0; ciphertext.len().saturating_sub(CHACHAPOLY_TAG)];
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Open the ciphertext
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        chachapoly_open_to(&mut buf, data, tag, ad, key, nonce)?;
+        Ok(buf)
+    }
+    /// Parses the `frame` (`le64(ad.len()) || ad || ciphertext || tag`), validates it and decrypts
+    /// the ciphertext into `buf`
+    ///
+    /// Returns the plaintext length and the range of the (now authenticated) AD within `frame`.
+    /// Returns `TruncatedInput` if `frame` is too short to contain the AD length, the AD and the
+    /// tag, and `InvalidData` if the authentication fails.
+    pub fn open_framed_parts(&self, buf: &mut[u8], frame: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<(usize, Range<usize>), ChachaPolyError>
+    {
+        // Parse the AD length
+        if frame.len() < 8 {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        let (mut ad_len, rest) = ([0; 8], &frame[8..]);
+        ad_len.copy_from_slice(&frame[..8]);
+        let ad_len = u64::from_le_bytes(ad_len);
+        if ad_len > rest.len() as u64 {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        let (ad, ciphertext) = rest.split_at(ad_len as usize);
+        
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Open the ciphertext
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        chachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok((data.len(), 8..8 + ad.len()))
+    }
+    /// Validates `ciphertext` (`ciphertext || tag`) with the embedded `ad`, decrypts it into `buf`
+    /// and ensures that the authenticated `ad` matches `expected_ad`
+    ///
+    /// This is for formats that carry the AD in an authenticated-but-cleartext region: the tag only
+    /// proves that `ad` has not been modified, but not that it is the AD the application expects
+    /// (e.g. a header that has been copied from another message under the same key). If `ad` and
+    /// `expected_ad` differ, `InvalidData` is returned and `buf` is erased like for an invalid tag;
+    /// on success, `ad` is authenticated and can be used as is. Returns the plaintext length.
+    pub fn open_with_ad_out(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8],
+        expected_ad: &[u8], key: &[u8], nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Open the ciphertext and compare the authenticated AD with the expected AD
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        chachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        if !eq_ct!(ad, expected_ad) {
+            buf[..data.len()].iter_mut().for_each(|b| *b = 0);
+            Err(ChachaPolyError::InvalidData)?
+        }
+        Ok(data.len())
+    }
+    /// Validates `ciphertext` (`ciphertext || tag`) with `ad` and decrypts it into `buf` without
+    /// branching on the validity of the tag
+    ///
+    /// Unlike `open_to`, the ciphertext is always decrypted into a scratch buffer – even if the tag
+    /// is invalid – and then copied into `buf` through a mask that is all zero if the tag is
+    /// invalid. So the same amount of work is done and the same bytes of `buf` are written in both
+    /// cases; on failure `buf[..ciphertext.len() - 16]` is zeroed. Returns the plaintext length.
+    ///
+    /// _Note: This costs an additional allocation and copy of the ciphertext and a full decryption
+    /// even for forged ciphertexts, so it is slower than `open_to` (especially if forgeries are
+    /// common); use it only if the timing of failed opens is security relevant for you._
+    pub fn open_ct(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Decrypt into the scratch buffer and validate the tag unconditionally
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        let mut scratch = data.to_vec();
+        ChaCha20Ietf::xor(key, nonce, 1, &mut scratch);
+        let mut vfy_tag = vec![0; CHACHAPOLY_TAG];
+        chachapoly_tag(&mut vfy_tag, data, ad, key, nonce);
+        let is_valid = verify_tag(tag, &vfy_tag);
+        
+        // Copy the plaintext through the mask and erase the scratch buffer
+        let mask = (is_valid as u8).wrapping_neg();
+        buf.iter_mut().zip(scratch.iter_mut()).for_each(|(b, s)| {
+            *b = and!(*s, mask);
+            *s = 0;
+        });
+        
+        match is_valid {
+            true => count_open("ChachaPolyIetf"),
+            false => {
+                count_auth_failure("ChachaPolyIetf");
+                Err(ChachaPolyError::InvalidData)?
+            }
+        }
+        Ok(data.len())
+    }
+    /// Splits a combined `ciphertext || tag` into the ciphertext and the tag
+    ///
+    /// Returns `TruncatedInput` if `ciphertext` is shorter than the tag (like `open` does).
+    pub fn split_tag(ciphertext: &[u8]) -> Result<(&[u8], &[u8; CHACHAPOLY_TAG]), ChachaPolyError> {
+        let len = ciphertext.len().checked_sub(CHACHAPOLY_TAG)
+            .ok_or(ChachaPolyError::TruncatedInput)?;
+        let (data, tag) = ciphertext.split_at(len);
+        Ok((data, tag.try_into().expect("The tag has an invalid size")))
+    }
+    /// Creates a nonce from a 4 byte `salt` followed by the 8 byte message `counter` in the given
+    /// byte order (`salt || counter`)
+    ///
+    /// Returns `ApiMisuse(SaltLen)` if `salt` is not exactly 4 bytes long (use 4 zero bytes if your
+    /// protocol has no salt). _Note: The counter must never repeat for the same key._
+    pub fn nonce_from_counter(salt: &[u8], counter: u64, endian: Endian)
+        -> Result<[u8; CHACHAPOLY_NONCE], ChachaPolyError>
+    {
+        let mut nonce = [0; CHACHAPOLY_NONCE];
+        nonce_from_counter(&mut nonce, salt, counter, endian)?;
+        Ok(nonce)
+    }
+    /// Checks whether a (negotiated) tag length of `len` bytes is supported
+    ///
+    /// This crate does not support truncated tags, so only the full 16 byte tag is accepted; a
+    /// handshake can use this predicate to reject other tag lengths cleanly.
+    pub const fn supports_tag_len(len: usize) -> bool {
+        len == CHACHAPOLY_TAG
+    }
+    /// Computes how many more bytes may be sealed under the same key/nonce combination if
+    /// `bytes_already_sealed` bytes have already been processed with it (e.g. by a long-lived
+    /// session that uses `ChaCha20Ietf::xor` with a running block counter)
+    ///
+    /// Returns `0` if the limit (`(2^32 - 1) * 64` bytes on 64 bit targets) has been reached; in
+    /// this case you must rotate the key or nonce before sealing more data.
+    pub const fn remaining_capacity(bytes_already_sealed: usize) -> usize {
+        CHACHAPOLY_MAX.saturating_sub(bytes_already_sealed)
+    }
+    /// Derives a fresh key from `key` and `nonce` (e.g. to advance the key of a long-lived session
+    /// for forward secrecy)
+    ///
+    /// The new key is the second half of the ChaCha20-IETF keystream block `0` for `key`/`nonce`.
+    /// ChachaPoly-IETF uses only the first half of this block (as one-time Poly1305 key) and
+    /// discards the rest, so the new key is never exposed by messages sealed under `key`, and the
+    /// old key cannot be recovered from the new one. The derivation is deterministic, so both
+    /// sides of a session must rekey with the same `nonce` at the same position.
+    pub fn rekey(key: &[u8; CHACHAPOLY_KEY], nonce: &[u8; CHACHAPOLY_NONCE])
+        -> [u8; CHACHAPOLY_KEY]
+    {
+        let mut block = [0; 64];
+        chacha20_ietf_block(key, nonce, 0, &mut block);
+        
+        let mut new_key = [0; CHACHAPOLY_KEY];
+        new_key.copy_from_slice(&block[32..]);
+        new_key
+    }
+    /// Runs the same parameter checks as `seal`/`seal_to` for the given lengths without performing
+    /// any cryptographic operation
+    ///
+    /// This allows to reject a whole batch of malformed requests up front; if this function
+    /// succeeds, `seal_to` with parameters of the same lengths will not fail.
+    pub fn validate_params(key_len: usize, nonce_len: usize, plaintext_len: usize, buf_len: usize)
+        -> Result<(), ChachaPolyError>
+    {
+        vfy_seal!(
+            key_len => [CHACHAPOLY_KEY], nonce_len => [CHACHAPOLY_NONCE],
+            plaintext_len => [buf_len, CHACHAPOLY_MAX]
+        );
+        Ok(())
+    }
+    
+    /// Encrypts `plaintext` and authenticates it with `ad` into `buf` using a nonce that is derived
+    /// deterministically from `key`, `ad` and `plaintext`; the layout is `nonce || ciphertext || tag`
+    ///
+    /// `buf` must hold `12 + plaintext.len() + 16` bytes. Returns the sealed length. The nonce is
+    /// a PRF (HChaCha20) over a Poly1305 hash of `ad` and `plaintext` under subkeys of `key`, so
+    /// no separate key is necessary.
+    ///
+    /// ## Security properties:
+    ///  - Sealing the same `plaintext` and `ad` under the same `key` always yields the same output,
+    ///    so an observer can tell if a message is repeated. This is the inherent price of
+    ///    deterministic encryption.
+    ///  - Different messages get different nonces unless the 96 bit nonces collide; a collision
+    ///    becomes likely after about `2^48` messages and reveals the XOR of the two plaintexts.
+    ///  - This is __NOT__ a proven SIV/MRAE construction (like AES-GCM-SIV); it is only a pragmatic
+    ///    protection against nonce reuse. If you can manage unique nonces, use `seal_to` instead.
+    ///  - The nonce subkeys are derived with their own constants, so they are not revealed by
+    ///    anything else that is computed under `key`. However, the synthetic nonce is an ordinary
+    ///    ChachaPoly-IETF nonce: if `key` is also used with `seal_to` (or any other method), a nonce
+    ///    chosen there may coincide with a synthetic nonce, which breaks confidentiality and
+    ///    integrity of both messages. Use a dedicated key for deterministic sealing.
+    pub fn seal_deterministic(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        if key.len() != CHACHAPOLY_KEY {
+            let misuse = Misuse::KeyLen{ expected: CHACHAPOLY_KEY, got: key.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        if plaintext.len() > CHACHAPOLY_MAX {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        let needed = CHACHAPOLY_NONCE + plaintext.len() + CHACHAPOLY_TAG;
+        if buf.len() < needed {
+            let misuse = Misuse::BufferTooSmall{ needed, got: buf.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Derive the nonce and seal the data behind it
+        let (nonce, sealed) = buf.split_at_mut(CHACHAPOLY_NONCE);
+        let (data, tag) = split_tag_mut(sealed, plaintext.len(), CHACHAPOLY_TAG)?;
+        chachapoly_synthetic_nonce(nonce, plaintext, ad, key);
+        data.copy_from_slice(plaintext);
+        chachapoly_seal(data, tag, ad, key, nonce);
+        Ok(needed)
+    }
+    /// Validates `sealed` (`nonce || ciphertext || tag`) created by `seal_deterministic` with `ad`
+    /// and decrypts it into `buf`
+    ///
+    /// Returns the plaintext length.
+    pub fn open_deterministic(&self, buf: &mut[u8], sealed: &[u8], ad: &[u8], key: &[u8])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Split the nonce from the ciphertext
+        if sealed.len() < CHACHAPOLY_NONCE {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        let (nonce, ciphertext) = sealed.split_at(CHACHAPOLY_NONCE);
+        
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        chachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok(data.len())
+    }
+    
+    /// Seals the plaintext in `data` with `ad` in place and appends the tag so that `data` contains
+    /// `ciphertext || tag` afterwards
+    ///
+    /// `data` may be empty. If the input is invalid, `data` is left unchanged.
+    pub fn seal_vec(&self, data: &mut Vec<u8>, ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            data => [data, CHACHAPOLY_MAX]
+        );
+        
+        // Make room for the tag and seal in place
+        let plaintext_len = data.len();
+        data.resize(plaintext_len + CHACHAPOLY_TAG, 0);
+        let (data, tag) = data.split_at_mut(plaintext_len);
+        chachapoly_seal(data, tag, ad, key, nonce);
+        Ok(())
+    }
+    /// Opens `data` (`ciphertext || tag`) with `ad` in place and truncates it to the plaintext
+    ///
+    /// If the input is invalid or the validation fails, `data` is left unchanged.
+    pub fn open_vec(&self, data: &mut Vec<u8>, ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            data => [data, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Open in place and remove the tag
+        let plaintext_len = data.len() - CHACHAPOLY_TAG;
+        let (plaintext, tag) = data.split_at_mut(plaintext_len);
+        chachapoly_open(plaintext, tag, ad, key, nonce)?;
+        data.truncate(plaintext_len);
+        Ok(())
+    }
+    
+    /// Computes the authentication tag for `ciphertext` (without tag) with `ad` without decrypting
+    /// it
+    ///
+    /// This is useful to recompute the expected tag for a ciphertext that has been produced
+    /// elsewhere under the same `key` and `nonce`; the tag is the same as the one produced by
+    /// `seal_to` for the corresponding plaintext. Compare the returned `Tag` with the received one
+    /// to get a constant-time comparison.
+    pub fn compute_tag(&self, ciphertext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Tag, ChachaPolyError>
+    {
+        // Verify input
+        vfy_dec!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [ciphertext, CHACHAPOLY_MAX]
+        );
+        
+        // Compute the tag
+        let mut tag = Tag([0; CHACHAPOLY_TAG]);
+        chachapoly_tag(&mut tag.0, ciphertext, ad, key, nonce);
+        Ok(tag)
+    }
+    /// Validates each record (`(ad, ciphertext || tag, nonce)`) of an append-only log of records
+    /// that have been sealed independently under `key`
+    ///
+    /// The records are only validated, not decrypted. Returns the amount of validated records or
+    /// the index of the first invalid record together with the error (e.g. `InvalidData` if the
+    /// record has been tampered with or `TruncatedInput` if it is shorter than a tag).
+    ///
+    /// _Note: Each record is validated on its own, so reordered or removed records are only
+    /// detected if the nonce or the AD binds the record to its position (e.g. a record counter)._
+    pub fn verify_log<'a>(&self,
+        records: impl Iterator<Item = (&'a [u8], &'a [u8], &'a [u8; CHACHAPOLY_NONCE])>, key: &[u8])
+        -> Result<usize, (usize, ChachaPolyError)>
+    {
+        /// Validates a single record
+        fn verify_record(ad: &[u8], ciphertext: &[u8], nonce: &[u8], key: &[u8])
+            -> Result<(), ChachaPolyError>
+        {
+            // Verify input
+            vfy_open!(
+                key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+                ciphertext => [ciphertext, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+            );
+            
+            // Recompute and validate the tag
+            let (data, tag) = ChachaPolyIetf::split_tag(ciphertext)?;
+            let mut vfy_tag = [0; CHACHAPOLY_TAG];
+            chachapoly_tag(&mut vfy_tag, data, ad, key, nonce);
+            match verify_tag(tag, &vfy_tag) {
+                true => Ok(()),
+                false => {
+                    count_auth_failure("ChachaPolyIetf");
+                    Err(ChachaPolyError::InvalidData)
+                }
+            }
+        }
+        
+        // Validate the records in order
+        let mut count = 0;
+        for (ad, ciphertext, nonce) in records {
+            verify_record(ad, ciphertext, nonce, key).map_err(|e| (count, e))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+    /// Validates `ciphertext` with `ad` against the detached `tag` and decrypts it into `buf`
+    ///
+    /// This is the counterpart to `open_to` for formats where the ciphertext and the tag are stored
+    /// in separate buffers. Returns the plaintext length (which is always `ciphertext.len()`).
+    pub fn open_detached_to(&self, buf: &mut[u8], ciphertext: &[u8], tag: &[u8; CHACHAPOLY_TAG],
+        ad: &[u8], key: &[u8], nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_dec!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_MAX]
+        );
+        
+        // Copy the ciphertext into buf and decrypt in place
+        chachapoly_open_to(buf, ciphertext, tag, ad, key, nonce)?;
+        Ok(ciphertext.len())
+    }
+    
+    /// Encrypts `plaintext` and authenticates it with `ad` into `buf` using the tag-prefixed
+    /// layout `tag || ciphertext`
+    ///
+    /// This is the counterpart to `seal_to` for formats that place the tag in front of the
+    /// ciphertext; the tag itself is computed exactly as for `seal_to`. Returns the sealed length.
+    pub fn seal_tag_prefixed(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8],
+        nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_seal!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            plaintext => [buf, CHACHAPOLY_MAX]
+        );
+        
+        // Copy the plaintext behind the tag and seal in place
+        let (tag, data) = buf.split_at_mut(CHACHAPOLY_TAG);
+        let data = &mut data[..plaintext.len()];
+        data.copy_from_slice(plaintext);
+        chachapoly_seal(data, tag, ad, key, nonce);
+        Ok(CHACHAPOLY_TAG + plaintext.len())
+    }
+    /// Validates the tag-prefixed `ciphertext` (`tag || ciphertext`) with `ad` and decrypts it into
+    /// `buf`
+    ///
+    /// This is the counterpart to `open_to` for formats that place the tag in front of the
+    /// ciphertext. Returns the plaintext length.
+    pub fn open_tag_prefixed(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], key: &[u8],
+        nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_open!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            ciphertext => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
+        );
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let (tag, data) = ciphertext.split_at(CHACHAPOLY_TAG);
+        chachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok(data.len())
+    }
+    
+    /// Encrypts `plaintext` into `buf` and authenticates it with `ad` like `seal_to`, but takes the
+    /// key and the nonce as arrays so that their lengths are checked at compile time
+    ///
+    /// Returns the sealed length.
+    pub fn seal_to_arr(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8],
+        key: &[u8; CHACHAPOLY_KEY], nonce: &[u8; CHACHAPOLY_NONCE])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        if plaintext.len() > CHACHAPOLY_MAX {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        
+        // Copy the plaintext into buf and seal in place
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), CHACHAPOLY_TAG)?;
+        data.copy_from_slice(plaintext);
+        chachapoly_seal(data, tag, ad, key, nonce);
+        Ok(plaintext.len() + CHACHAPOLY_TAG)
+    }
+    /// Validates `ciphertext` with `ad` and decrypts it into `buf` like `open_to`, but takes the
+    /// key and the nonce as arrays so that their lengths are checked at compile time
+    ///
+    /// Returns the plaintext length.
+    pub fn open_to_arr(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8],
+        key: &[u8; CHACHAPOLY_KEY], nonce: &[u8; CHACHAPOLY_NONCE])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        if ciphertext.len() > CHACHAPOLY_MAX {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        let data_len = ciphertext.len().checked_sub(CHACHAPOLY_TAG)
+            .ok_or(ChachaPolyError::TruncatedInput)?;
+        if buf.len() < data_len {
+            let misuse = Misuse::BufferTooSmall{ needed: data_len, got: buf.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let (data, tag) = ciphertext.split_at(data_len);
+        chachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok(data_len)
+    }
+    
+    /// Seals `plaintext` with `ad` into `buf` for multiple recipients
+    ///
+    /// This generates a random content encryption key (CEK) which is used to seal the payload once;
+    /// the CEK is then sealed under each key in `recipient_keys` with a random nonce and the payload
+    /// tag as AD. Returns the length of the sealed payload in `buf` and the wrapped CEKs in the same
+    /// order as `recipient_keys`. Each recipient can open the payload with `open_with_wrapped_cek`.
+    /// The CEK is held in a `SecretKey` and is thus erased if the `zeroize` feature is enabled.
+    ///
+    /// Because the payload tag authenticates the payload and its AD under the CEK, a wrapped CEK
+    /// is bound to this payload and cannot be moved to another envelope sealed for the same
+    /// recipient.
+    ///
+    /// _Warning: Because the wrapping nonces are random 96 bit values, a single recipient key must
+    /// not be used to wrap more than 2^32 CEKs; otherwise the probability of a nonce collision
+    /// exceeds 2^-32._
+    ///
+    /// _Note: Each recipient can decrypt the CEK and could thus forge a payload that is accepted
+    /// by the other recipients; this construction provides confidentiality and integrity against
+    /// outsiders only._
+    pub fn seal_with_random_cek(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8],
+        recipient_keys: &[&[u8; CHACHAPOLY_KEY]], rng: &mut dyn SecureRng)
+        -> Result<(usize, Vec<WrappedCek>), Box<dyn Error + 'static>>
+    {
+        // Generate the CEK and seal the payload
+        let cek = SecretKey::generate(rng)?;
+        let len = self.seal_to(buf, plaintext, ad, &cek, &PAYLOAD_NONCE)?;
+        let payload_tag = &buf[len - CHACHAPOLY_TAG..len];
+        
+        // Wrap the CEK for each recipient
+        let mut wrapped_ceks = Vec::with_capacity(recipient_keys.len());
+        for key in recipient_keys {
+            let mut wrapped_cek = [0; CHACHAPOLY_WRAPPED_CEK];
+            let (nonce, sealed_cek) = wrapped_cek.split_at_mut(CHACHAPOLY_NONCE);
+            rng.random(nonce)?;
+            self.seal_to(sealed_cek, &cek, payload_tag, *key, nonce)?;
+            wrapped_ceks.push(wrapped_cek);
+        }
+        Ok((len, wrapped_ceks))
+    }
+    /// Unwraps the content encryption key from `wrapped_cek` using the recipient's `key` and opens
+    /// `ciphertext` with `ad` into `buf`
+    ///
+    /// The CEK only unwraps if `wrapped_cek` was created for this `ciphertext`.
+    pub fn open_with_wrapped_cek(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8],
+        wrapped_cek: &WrappedCek, key: &[u8; CHACHAPOLY_KEY])
+        -> Result<usize, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        if ciphertext.len() < CHACHAPOLY_TAG {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        let payload_tag = &ciphertext[ciphertext.len() - CHACHAPOLY_TAG..];
+        
+        // Unwrap the CEK
+        let (nonce, sealed_cek) = wrapped_cek.split_at(CHACHAPOLY_NONCE);
+        let mut cek = SecretKey::zeroed();
+        self.open_to(cek.as_mut_bytes(), sealed_cek, payload_tag, key, nonce)?;
+        
+        // Open the payload
+        self.open_to(buf, ciphertext, ad, &cek, &PAYLOAD_NONCE)
+    }
 }
 impl SecKeyGen for ChachaPolyIetf {
     fn new_sec_key(&self, buf: &mut[u8], rng: &mut dyn SecureRng) -> Result<usize, Box<dyn Error + 'static>> {
@@ -92,7 +949,7 @@ impl Cipher for ChachaPolyIetf {
     }
     
     fn encrypted_len_max(&self, plaintext_len: usize) -> usize {
-        plaintext_len + 16
+        chachapoly_ct_len(plaintext_len)
     }
     
     fn encrypt(&self, buf: &mut[u8], plaintext_len: usize, key: &[u8], nonce: &[u8])
@@ -128,8 +985,8 @@ impl AeadCipher for ChachaPolyIetf {
         );
         
         // Seal the data
-        let (data, tag) = buf.split_at_mut(plaintext_len);
-        chachapoly_seal(data, &mut tag[..CHACHAPOLY_TAG], ad, key, nonce);
+        let (data, tag) = split_tag_mut(buf, plaintext_len, CHACHAPOLY_TAG)?;
+        chachapoly_seal(data, tag, ad, key, nonce);
         Ok(plaintext_len + CHACHAPOLY_TAG)
     }
     fn seal_to(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
@@ -142,9 +999,9 @@ impl AeadCipher for ChachaPolyIetf {
         );
         
         // Copy the plaintext into buf and seal in place
-        let (data, tag) = buf.split_at_mut(plaintext.len());
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), CHACHAPOLY_TAG)?;
         data.copy_from_slice(plaintext);
-        chachapoly_seal(data, &mut tag[..CHACHAPOLY_TAG], ad, key, nonce);
+        chachapoly_seal(data, tag, ad, key, nonce);
         Ok(plaintext.len() + CHACHAPOLY_TAG)
     }
     
@@ -152,15 +1009,16 @@ impl AeadCipher for ChachaPolyIetf {
         -> Result<usize, Box<dyn Error + 'static>>
     {
         // Verify input
-        vfy_open!(
+        vfy_open_in_place!(
             key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
             ciphertext_len => [buf, CHACHAPOLY_TAG, CHACHAPOLY_MAX]
         );
         
         // Open the data
-        let (data, tag) = buf.split_at_mut(ciphertext_len - CHACHAPOLY_TAG);
-        chachapoly_open(data, &tag[..CHACHAPOLY_TAG], ad, key, nonce)?;
-        Ok(ciphertext_len - CHACHAPOLY_TAG)
+        let plaintext_len = ciphertext_len - CHACHAPOLY_TAG;
+        let (data, tag) = split_tag_mut(buf, plaintext_len, CHACHAPOLY_TAG)?;
+        chachapoly_open(data, tag, ad, key, nonce)?;
+        Ok(plaintext_len)
     }
     fn open_to(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
         -> Result<usize, Box<dyn Error + 'static>>
@@ -172,9 +1030,8 @@ impl AeadCipher for ChachaPolyIetf {
         );
         
         // Copy the ciphertext into buf and decrypt in place
-        let (data, tag) = ciphertext.split_at(ciphertext.len() - CHACHAPOLY_TAG);
-        buf[..data.len()].copy_from_slice(data);
-        chachapoly_open(&mut buf[..data.len()], &tag[..CHACHAPOLY_TAG], ad, key, nonce)?;
-        Ok(ciphertext.len() - CHACHAPOLY_TAG)
+        let (data, tag) = Self::split_tag(ciphertext)?;
+        chachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok(data.len())
     }
 }
\ No newline at end of file
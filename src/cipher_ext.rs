@@ -0,0 +1,36 @@
+use crypto_api::cipher::Cipher;
+use std::error::Error;
+
+
+/// An extension for `Cipher` implementations (including `dyn Cipher` trait objects) that allocates
+/// the output buffer
+pub trait CipherExt {
+    /// Encrypts `plaintext` into a newly allocated vector
+    ///
+    /// The vector is sized via `encrypted_len_max` and truncated to the actual ciphertext length.
+    fn encrypt_vec(&self, plaintext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Vec<u8>, Box<dyn Error + 'static>>;
+    /// Decrypts `ciphertext` into a newly allocated vector
+    ///
+    /// The vector is sized to `ciphertext.len()` and truncated to the actual plaintext length.
+    fn decrypt_vec(&self, ciphertext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Vec<u8>, Box<dyn Error + 'static>>;
+}
+impl<T: Cipher + ?Sized> CipherExt for T {
+    fn encrypt_vec(&self, plaintext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Vec<u8>, Box<dyn Error + 'static>>
+    {
+        let mut buf = vec![0; self.encrypted_len_max(plaintext.len())];
+        let len = self.encrypt_to(&mut buf, plaintext, key, nonce)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+    fn decrypt_vec(&self, ciphertext: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Vec<u8>, Box<dyn Error + 'static>>
+    {
+        let mut buf = vec![0; ciphertext.len()];
+        let len = self.decrypt_to(&mut buf, ciphertext, key, nonce)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
\ No newline at end of file
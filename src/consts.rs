@@ -0,0 +1,15 @@
+pub use crate::{
+    chacha20_ietf::{ CHACHA20_MAX, CHACHA20_KEY, CHACHA20_NONCE },
+    chacha20::{ CHACHA20_ORIG_MAX, CHACHA20_ORIG_KEY, CHACHA20_ORIG_NONCE },
+    xchacha20::{ XCHACHA20_MAX, XCHACHA20_KEY, XCHACHA20_NONCE },
+    poly1305::{ POLY1305_KEY, POLY1305_TAG },
+    chachapoly_ietf::{
+        CHACHAPOLY_MAX, CHACHAPOLY_AD_MAX, CHACHAPOLY_KEY, CHACHAPOLY_NONCE, CHACHAPOLY_TAG
+    },
+    chachapoly::{
+        CHACHAPOLY_ORIG_MAX, CHACHAPOLY_ORIG_KEY, CHACHAPOLY_ORIG_NONCE, CHACHAPOLY_ORIG_TAG
+    },
+    xchachapoly::{ XCHACHAPOLY_MAX, XCHACHAPOLY_KEY, XCHACHAPOLY_NONCE, XCHACHAPOLY_TAG },
+    stream::{ CHACHAPOLY_STREAM_CHUNK, CHACHAPOLY_STREAM_PREFIX },
+    envelope::CHACHAPOLY_WRAPPED_CEK
+};
\ No newline at end of file
@@ -3,7 +3,11 @@ const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
 
 
 /// Performs the ChaCha20 rounds over `state`
-fn chacha20_rounds(state: &mut[u32]) {
+///
+/// This is the portable scalar implementation which is always used if the `force-soft` feature is
+/// enabled (currently it is the only implementation)
+#[inline]
+pub fn chacha20_rounds(state: &mut[u32]) {
     for _ in 0..10 {
         /// A ChaCha20 quarterround
         macro_rules! quarterround {
@@ -38,12 +42,31 @@ fn chacha20_rounds(state: &mut[u32]) {
 
 /// A HChaCha20 implementation
 pub fn hchacha20_hash(key: &[u8], nonce: &[u8], buf: &mut[u8]) {
+    #[cfg(feature = "test-instrumentation")]
+    crate::instrumentation::count_hchacha20();
+    
     // Create and init state
     let mut state = vec![0u32; 16];
     ( 0.. 4).for_each(|i| state[i] = CONSTANTS[i]);
     ( 4..12).for_each(|i| state[i] = read32_le!(  &key[(i -  4) * 4..]));
     (12..16).for_each(|i| state[i] = read32_le!(&nonce[(i - 12) * 4..]));
-    
+    hchacha20_finish(state, buf)
+}
+/// Derives a subkey from `key` into `buf` like HChaCha20 with an all-zero nonce, but with `label`
+/// in place of the ChaCha20 constants
+///
+/// Every ChaCha20, XChaCha20 and HChaCha20 state starts with the ChaCha20 constants, so as long as
+/// `label` differs from them, the derived subkey is unrelated to any keystream block or HChaCha20
+/// output under `key`.
+pub fn chacha20_derive_key(label: &[u8; 16], key: &[u8], buf: &mut[u8]) {
+    // Create and init state
+    let mut state = vec![0u32; 16];
+    ( 0.. 4).for_each(|i| state[i] = read32_le!(&label[i * 4..]));
+    ( 4..12).for_each(|i| state[i] = read32_le!(  &key[(i -  4) * 4..]));
+    hchacha20_finish(state, buf)
+}
+/// Mixes the initialized HChaCha20 `state` and writes the output words to `buf`
+fn hchacha20_finish(mut state: Vec<u32>, buf: &mut[u8]) {
     // Mix the state
     chacha20_rounds(&mut state);
     
@@ -55,7 +78,17 @@ pub fn hchacha20_hash(key: &[u8], nonce: &[u8], buf: &mut[u8]) {
 
 
 /// Computes the `n`th ChaCha20-IETF block with `key` and `nonce` into `buf`
+#[inline]
 pub fn chacha20_ietf_block(key: &[u8], nonce: &[u8], n: u32, buf: &mut[u8]) {
+    chacha20_ietf_blocks(key, nonce, n, &mut buf[..64])
+}
+/// Computes the consecutive ChaCha20-IETF blocks starting at the `n`th block with `key` and `nonce`
+/// into `buf` (whose length must be a multiple of 64)
+///
+/// The state is only created and initialized once, so this is faster than calling
+/// `chacha20_ietf_block` for each block. The block counter wraps around; the caller must ensure
+/// that it does not exceed `2^32 - 1`.
+pub fn chacha20_ietf_blocks(key: &[u8], nonce: &[u8], n: u32, buf: &mut[u8]) {
     // Create state buffer
     let mut state = vec![0u32; 32];
     let (init, mixed) = state.split_at_mut(16);
@@ -63,20 +96,23 @@ pub fn chacha20_ietf_block(key: &[u8], nonce: &[u8], n: u32, buf: &mut[u8]) {
     // Init state
     ( 0.. 4).for_each(|i| init[i] = CONSTANTS[i]);
     ( 4..12).for_each(|i| init[i] = read32_le!(  &key[(i -  4) * 4..]));
-    init[12] = n;
     (13..16).for_each(|i| init[i] = read32_le!(&nonce[(i - 13) * 4..]));
     
-    // Mix state
-    mixed.copy_from_slice(init);
-    chacha20_rounds(mixed);
-    
-    // Add init state to mixed state and write the mixed state to the buffer
-    ( 0..16).for_each(|i| mixed[i] = add!(mixed[i], init[i]));
-    ( 0..16).for_each(|i| write32_le!(mixed[i] => &mut buf[i * 4..]));
+    for (i, block) in buf.chunks_mut(64).enumerate() {
+        // Set the counter and mix state
+        init[12] = add!(n, i as u32);
+        mixed.copy_from_slice(init);
+        chacha20_rounds(mixed);
+        
+        // Add init state to mixed state and write the mixed state to the buffer
+        ( 0..16).for_each(|i| mixed[i] = add!(mixed[i], init[i]));
+        ( 0..16).for_each(|i| write32_le!(mixed[i] => &mut block[i * 4..]));
+    }
 }
 
 
 /// Computes the `n`th ChaCha20 block with `key` and `nonce` into `buf`
+#[inline]
 pub fn chacha20_block(key: &[u8], nonce: &[u8], n: u64, buf: &mut[u8]) {
     // Create state buffer
     let mut state = vec![0u32; 32];
@@ -26,6 +26,7 @@ pub fn poly1305_init(r: &mut[u32], s: &mut[u32], u: &mut[u32], key: &[u8]) {
 ///
 /// _Warning: This implementation will pad __ANY__ incomplete block with `0` bytes; the `is_last`
 /// switch indicates where the high bit should be appended_
+#[inline]
 pub fn poly1305_update(a: &mut[u32], r: &[u32], u: &[u32], mut data: &[u8], is_last: bool) {
     // Prepare buffer and `w` to avoid unnecessary reallocations
     let mut buf = vec![0; 16];
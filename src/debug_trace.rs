@@ -0,0 +1,52 @@
+use crate::{
+    ChachaPolyError, ChaCha20Ietf, Poly1305,
+    chachapoly_ietf::{
+        ChachaPolyIetf, CHACHAPOLY_KEY, CHACHAPOLY_NONCE, CHACHAPOLY_TAG, CHACHAPOLY_MAX
+    }
+};
+
+
+/// The intermediate values of a ChachaPoly-IETF seal operation
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Trace {
+    /// The one-time Poly1305 key (the first 32 bytes of keystream block `0`)
+    pub poly_key: [u8; 32],
+    /// The ciphertext (the plaintext XORed with the keystream starting at block `1`)
+    pub ciphertext: Vec<u8>,
+    /// The footer (`le64(ad.len()) || le64(ciphertext.len())`)
+    pub footer: [u8; 16],
+    /// The authentication tag
+    pub tag: [u8; CHACHAPOLY_TAG]
+}
+
+
+impl ChachaPolyIetf {
+    /// Seals `plaintext` with `ad` and returns all intermediate values (e.g. to document new test
+    /// vectors)
+    ///
+    /// _Note: This exposes the one-time Poly1305 key and is only meant for authoring and reviewing
+    /// test vectors._
+    pub fn debug_trace(plaintext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Trace, ChachaPolyError>
+    {
+        // Verify input
+        vfy_dec!(
+            key => [CHACHAPOLY_KEY], nonce => [CHACHAPOLY_NONCE],
+            plaintext => [plaintext, CHACHAPOLY_MAX]
+        );
+        
+        // Compute the Poly1305 key, the ciphertext and the footer
+        let mut poly_key = [0; 32];
+        ChaCha20Ietf::xor(key, nonce, 0, &mut poly_key);
+        let mut ciphertext = plaintext.to_vec();
+        ChaCha20Ietf::xor(key, nonce, 1, &mut ciphertext);
+        let mut footer = [0; 16];
+        footer[..8].copy_from_slice(&(ad.len() as u64).to_le_bytes());
+        footer[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+        
+        // Compute the tag
+        let mut tag = [0; CHACHAPOLY_TAG];
+        Poly1305::chachapoly_auth(&mut tag, ad, &ciphertext, &footer, &poly_key);
+        Ok(Trace{ poly_key, ciphertext, footer, tag })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+use crate::chachapoly_ietf::{ CHACHAPOLY_KEY, CHACHAPOLY_NONCE, CHACHAPOLY_TAG };
+
+
+/// The size of a wrapped content encryption key (`nonce || sealed key || tag`)
+pub const CHACHAPOLY_WRAPPED_CEK: usize = CHACHAPOLY_NONCE + CHACHAPOLY_KEY + CHACHAPOLY_TAG;
+
+/// A wrapped content encryption key
+pub type WrappedCek = [u8; CHACHAPOLY_WRAPPED_CEK];
\ No newline at end of file
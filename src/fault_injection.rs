@@ -0,0 +1,22 @@
+use std::cell::Cell;
+
+
+thread_local! {
+    /// Whether the next keystream computation on this thread should be corrupted
+    static ARMED: Cell<bool> = const { Cell::new(false) };
+}
+
+
+/// Corrupts the first byte of the next ChaCha20-IETF keystream that is computed on this thread
+#[doc(hidden)]
+pub fn arm_keystream_fault() {
+    ARMED.with(|armed| armed.set(true))
+}
+
+
+/// Flips the lowest bit of the first byte in `keystream` if a fault has been armed and disarms it
+pub(in crate) fn corrupt_keystream(keystream: &mut[u8]) {
+    if ARMED.with(|armed| armed.replace(false)) {
+        keystream[0] ^= 0x01;
+    }
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+use std::cell::Cell;
+
+
+thread_local! {
+    /// The amount of HChaCha20 invocations on this thread
+    static HCHACHA20_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+
+/// Gets the amount of HChaCha20 invocations (i.e. XChaCha20 subkey derivations) on this thread
+#[doc(hidden)]
+pub fn hchacha20_calls() -> usize {
+    HCHACHA20_CALLS.with(|calls| calls.get())
+}
+
+
+/// Counts an HChaCha20 invocation
+pub(in crate) fn count_hchacha20() {
+    HCHACHA20_CALLS.with(|calls| calls.set(calls.get() + 1))
+}
\ No newline at end of file
@@ -2,16 +2,51 @@
 
 #[macro_use] pub mod core;
 #[macro_use] mod verify_input;
+mod chacha20;
 mod chacha20_ietf;
 mod xchacha20;
 mod poly1305;
+mod chachapoly;
 mod chachapoly_ietf;
 mod xchachapoly;
+mod secret_key;
+mod tag;
+mod nonce;
+mod envelope;
+mod ad_builder;
+mod versioned;
+mod cipher_ext;
+mod stream;
+mod telemetry;
+/// Stable, length-checked wrappers around the ChaCha20 and Poly1305 core primitives
+pub mod primitives;
+/// The key, nonce, tag and limit sizes of all algorithms (to size buffers)
+pub mod consts;
+/// The intermediate values of a seal operation (to author and review test vectors)
+#[cfg(feature = "debug-trace")]
+pub mod debug_trace;
+/// A test-only hook to deliberately corrupt the keystream (a negative control for the test suite)
+#[cfg(feature = "test-fault-injection")]
+#[doc(hidden)] pub mod fault_injection;
+/// A test-only counter to verify that expensive derivations are not performed redundantly
+#[cfg(feature = "test-instrumentation")]
+#[doc(hidden)] pub mod instrumentation;
+#[cfg(all(feature = "test-fault-injection", not(debug_assertions)))]
+compile_error!("The `test-fault-injection` feature must not be enabled in release builds");
 
 pub use crate::{
-    chacha20_ietf::ChaCha20Ietf, xchacha20::XChaCha20,
+    chacha20::ChaCha20, chacha20_ietf::ChaCha20Ietf, xchacha20::{ XChaCha20, XChaCha20Session },
     poly1305::Poly1305,
-    chachapoly_ietf::ChachaPolyIetf, xchachapoly::XChachaPoly
+    chachapoly_ietf::{
+        ChachaPolyIetf, CHACHAPOLY_AD_MAX, chachapoly_seal, chachapoly_open, chachapoly_ct_len,
+        chachapoly_pt_len
+    },
+    chachapoly::ChachaPoly, xchachapoly::XChachaPoly,
+    secret_key::SecretKey, tag::Tag, nonce::{ ChachaPolyNonce, XChachaPolyNonce, Endian },
+    envelope::WrappedCek, ad_builder::AdBuilder, versioned::{ VersionedAead, AeadAlgorithm },
+    stream::{ ChachaPolyWriter, ChachaPolyReader },
+    cipher_ext::CipherExt,
+    telemetry::{ SEAL_COUNTER, OPEN_COUNTER, AUTH_FAILURE_COUNTER }
 };
 pub use crypto_api;
 use std::{
@@ -20,13 +55,37 @@ use std::{
 };
 
 
+/// The kind of an API misuse
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Misuse {
+    /// The key has an invalid length (`expected` bytes are required but the key has `got` bytes)
+    KeyLen{ expected: usize, got: usize },
+    /// The nonce has an invalid length (`expected` bytes are required but the nonce has `got` bytes)
+    NonceLen{ expected: usize, got: usize },
+    /// The buffer does not have the exact required length
+    BufferLen,
+    /// The buffer is too small (`needed` bytes are required but the buffer has only `got` bytes)
+    BufferTooSmall{ needed: usize, got: usize },
+    /// There is more data than can be processed with one key/nonce combination
+    TooMuchData,
+    /// The input does not start with the expected magic bytes
+    InvalidMagic,
+    /// The algorithm identifier is unknown
+    UnknownAlgorithm(u8),
+    /// The salt has an invalid length (`expected` bytes are required but the salt has `got` bytes)
+    SaltLen{ expected: usize, got: usize }
+}
+
+
 /// A ChaChaPoly-related error
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum ChachaPolyError {
     /// The processed data is invalid (MAC-mismatch)
     InvalidData,
+    /// The input is too short to contain an authentication tag (i.e. it has been truncated)
+    TruncatedInput,
     /// An API misuse happened
-    ApiMisuse(&'static str)
+    ApiMisuse(Misuse)
 }
 impl Display for ChachaPolyError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
@@ -0,0 +1,117 @@
+use crate::{
+    ChachaPolyError, Misuse,
+    chachapoly_ietf::CHACHAPOLY_NONCE, xchachapoly::XCHACHAPOLY_NONCE
+};
+use std::{
+    ops::Deref,
+    convert::{ TryFrom, TryInto }
+};
+
+
+/// The byte order of a counter within a nonce
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Endian {
+    /// Big endian (most significant byte first)
+    Big,
+    /// Little endian (least significant byte first)
+    Little
+}
+
+
+/// Assembles `nonce` from `salt` followed by the 8 byte `counter` in the given byte order
+pub(in crate) fn nonce_from_counter(nonce: &mut[u8], salt: &[u8], counter: u64, endian: Endian)
+    -> Result<(), ChachaPolyError>
+{
+    // Verify input
+    let salt_len = nonce.len() - 8;
+    if salt.len() != salt_len {
+        Err(ChachaPolyError::ApiMisuse(Misuse::SaltLen{ expected: salt_len, got: salt.len() }))?
+    }
+    
+    // Assemble the nonce
+    let counter = match endian {
+        Endian::Big => counter.to_be_bytes(),
+        Endian::Little => counter.to_le_bytes()
+    };
+    nonce[..salt_len].copy_from_slice(salt);
+    nonce[salt_len..].copy_from_slice(&counter);
+    Ok(())
+}
+
+
+/// A 96 bit nonce for ChachaPoly-IETF
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ChachaPolyNonce(pub [u8; CHACHAPOLY_NONCE]);
+impl ChachaPolyNonce {
+    /// Borrows `bytes` as nonce without copying or `None` if `bytes` is not exactly 12 bytes long
+    ///
+    /// _Note: Because this crate forbids `unsafe` code, the view is the underlying array reference
+    /// instead of `&Self`; it can be passed to all APIs that take a nonce as is._
+    pub fn from_slice(bytes: &[u8]) -> Option<&[u8; CHACHAPOLY_NONCE]> {
+        bytes.try_into().ok()
+    }
+}
+impl From<[u8; CHACHAPOLY_NONCE]> for ChachaPolyNonce {
+    fn from(nonce: [u8; CHACHAPOLY_NONCE]) -> Self {
+        Self(nonce)
+    }
+}
+impl TryFrom<&[u8]> for ChachaPolyNonce {
+    type Error = ChachaPolyError;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let nonce = Self::from_slice(bytes).ok_or(ChachaPolyError::ApiMisuse(Misuse::NonceLen{
+            expected: CHACHAPOLY_NONCE, got: bytes.len()
+        }))?;
+        Ok(Self(*nonce))
+    }
+}
+impl Deref for ChachaPolyNonce {
+    type Target = [u8; CHACHAPOLY_NONCE];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl AsRef<[u8]> for ChachaPolyNonce {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+
+/// A 192 bit nonce for XChachaPoly
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct XChachaPolyNonce(pub [u8; XCHACHAPOLY_NONCE]);
+impl XChachaPolyNonce {
+    /// Borrows `bytes` as nonce without copying or `None` if `bytes` is not exactly 24 bytes long
+    ///
+    /// _Note: Because this crate forbids `unsafe` code, the view is the underlying array reference
+    /// instead of `&Self`; it can be passed to all APIs that take a nonce as is._
+    pub fn from_slice(bytes: &[u8]) -> Option<&[u8; XCHACHAPOLY_NONCE]> {
+        bytes.try_into().ok()
+    }
+}
+impl From<[u8; XCHACHAPOLY_NONCE]> for XChachaPolyNonce {
+    fn from(nonce: [u8; XCHACHAPOLY_NONCE]) -> Self {
+        Self(nonce)
+    }
+}
+impl TryFrom<&[u8]> for XChachaPolyNonce {
+    type Error = ChachaPolyError;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let nonce = Self::from_slice(bytes).ok_or(ChachaPolyError::ApiMisuse(Misuse::NonceLen{
+            expected: XCHACHAPOLY_NONCE, got: bytes.len()
+        }))?;
+        Ok(Self(*nonce))
+    }
+}
+impl Deref for XChachaPolyNonce {
+    type Target = [u8; XCHACHAPOLY_NONCE];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl AsRef<[u8]> for XChachaPolyNonce {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
\ No newline at end of file
@@ -1,15 +1,23 @@
-use crate::core::poly1305::{ poly1305_init, poly1305_update, poly1305_finish };
+use crate::{
+    ChachaPolyError, Misuse,
+    core::poly1305::{ poly1305_init, poly1305_update, poly1305_finish }, tag::verify_tag
+};
 use crypto_api::{
     mac::{ MacInfo, Mac },
     rng::{ SecureRng, SecKeyGen }
 };
-use std::error::Error;
+use std::{
+    error::Error,
+    io::{ self, Read, ErrorKind }
+};
 
 
 /// The size of a Poly1305 key (256 bits/32 bytes)
 pub const POLY1305_KEY: usize = 32;
 /// The size of a ChaChaPoly authentication tag
 pub const POLY1305_TAG: usize = 16;
+/// The size of the chunks that are read from an AD reader (must be a multiple of 16)
+const POLY1305_READ_CHUNK: usize = 4096;
 
 
 /// An implementation of [Poly1305](https://tools.ietf.org/html/rfc8439)
@@ -20,6 +28,94 @@ impl Poly1305 {
         Box::new(Self)
     }
     
+    /// Authenticates `data` into `buf` using `key` with configurable final block padding
+    ///
+    /// If `pad_final` is `true`, an incomplete final block is padded as specified in
+    /// [RFC 8439](https://tools.ietf.org/html/rfc8439#section-2.5.1): a `0x01` byte is appended and
+    /// the rest of the block is filled with `0x00` bytes; this is exactly what `Mac::auth` does.
+    ///
+    /// If `pad_final` is `false`, an incomplete final block is only filled with `0x00` bytes and is
+    /// then processed like a complete block (i.e. with the high bit `2^128` set). This is how the
+    /// ChachaPoly-IETF construction processes the AD and the ciphertext.
+    ///
+    /// ## Warning:
+    /// `pad_final = false` is __NOT__ the standard Poly1305 MAC. It exists for interoperability with
+    /// legacy or non-standard MAC formats only – use `Mac::auth` if you don't know that you need
+    /// this. Note that both variants produce the same tag if `data.len()` is a multiple of 16.
+    pub fn auth_raw(buf: &mut[u8], data: &[u8], key: &[u8], pad_final: bool)
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_auth!(key => [POLY1305_KEY], => [buf, POLY1305_TAG]);
+        
+        // Authenticate data
+        let (mut r, mut s, mut u, mut a) = (vec![0; 5], vec![0; 4], vec![0; 5], vec![0; 5]);
+        poly1305_init(&mut r, &mut s, &mut u, key);
+        poly1305_update(&mut a, &r, &u, data, pad_final);
+        poly1305_finish(buf, &mut a, &s);
+        
+        Ok(POLY1305_TAG)
+    }
+    
+    /// Authenticates `data` into `buf` using a key that is split into the halves `r` and `s`
+    ///
+    /// This is equivalent to `Mac::auth` with the 32 byte key `r || s`. `r` is always clamped as
+    /// specified in [RFC 8439](https://tools.ietf.org/html/rfc8439#section-2.5.1) (i.e. the top
+    /// four bits of the bytes 3, 7, 11 and 15 and the bottom two bits of the bytes 4, 8 and 12 are
+    /// cleared); since clamping is idempotent, it does not matter whether `r` is already clamped or
+    /// not. `s` is used as is.
+    pub fn auth_with_rs(buf: &mut[u8], data: &[u8], r: &[u8; 16], s: &[u8; 16])
+        -> Result<usize, ChachaPolyError>
+    {
+        let mut key = vec![0; POLY1305_KEY];
+        key[..16].copy_from_slice(r);
+        key[16..].copy_from_slice(s);
+        Self::auth_raw(buf, data, &key, true)
+    }
+    
+    /// Authenticates `data` into `buf` using `key` and compares the tag with `expected_tag` in
+    /// constant time
+    ///
+    /// This replaces the pattern of calling `Mac::auth` and comparing the tags manually (which is
+    /// easily done in non-constant time). Returns `InvalidData` if the tags do not match and
+    /// `ApiMisuse(BufferLen)` if `expected_tag` is not exactly 16 bytes long.
+    pub fn verify_into(&self, buf: &mut[u8], data: &[u8], key: &[u8], expected_tag: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        if expected_tag.len() != POLY1305_TAG {
+            Err(ChachaPolyError::ApiMisuse(Misuse::BufferLen))?
+        }
+        
+        // Authenticate the data and compare the tags
+        Self::auth_raw(buf, data, key, true)?;
+        match verify_tag(&buf[..POLY1305_TAG], expected_tag) {
+            true => Ok(()),
+            false => Err(ChachaPolyError::InvalidData)
+        }
+    }
+    
+    /// Computes the ChachaPoly-IETF tag over `ad` and `ciphertext` using the one-time Poly1305
+    /// `key`
+    ///
+    /// This is exactly the MAC computation of ChachaPoly-IETF (`ad || pad16 || ciphertext || pad16
+    /// || le64(ad.len()) || le64(ciphertext.len())`) for custom AEAD constructions that perform the
+    /// encryption separately; `key` is usually the first 32 bytes of the ChaCha20-IETF keystream
+    /// block `0`. _Note: Compare the resulting tag only in constant time (e.g. via `Tag`)._
+    pub fn chachapoly_mac(ad: &[u8], ciphertext: &[u8], key: &[u8; POLY1305_KEY])
+        -> [u8; POLY1305_TAG]
+    {
+        // Create the footer
+        let mut foot = [0; 16];
+        foot[..8].copy_from_slice(&(ad.len() as u64).to_le_bytes());
+        foot[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+        
+        // Compute the tag
+        let mut tag = [0; POLY1305_TAG];
+        Self::chachapoly_auth(&mut tag, ad, ciphertext, &foot, key);
+        tag
+    }
+    
     /// A helper function for the ChachaPoly-IETF AEAD construction
     pub(in crate) fn chachapoly_auth(tag: &mut[u8], ad: &[u8], data: &[u8], foot: &[u8], key: &[u8]) {
         // Init Poly1305
@@ -32,6 +128,64 @@ impl Poly1305 {
         poly1305_update(&mut a, &r, &u, foot, true);
         poly1305_finish(tag, &mut a, &mut s);
     }
+    
+    /// A helper function for the ChachaPoly-IETF AEAD construction that reads the AD from `ad`
+    ///
+    /// The AD is processed in chunks of `POLY1305_READ_CHUNK` bytes; since they are aligned to the
+    /// block size, only the final chunk may be incomplete and is padded exactly like a contiguous
+    /// AD would be.
+    pub(in crate) fn chachapoly_auth_reader(tag: &mut[u8], ad: &mut dyn Read, data: &[u8],
+        key: &[u8]) -> io::Result<()>
+    {
+        // Init Poly1305
+        let (mut r, mut s, mut u, mut a) = (vec![0; 5], vec![0; 4], vec![0; 5], vec![0; 5]);
+        poly1305_init(&mut r, &mut s, &mut u, key);
+        
+        // Process the AD chunkwise
+        let (mut buf, mut ad_len) = (vec![0; POLY1305_READ_CHUNK], 0u64);
+        loop {
+            // Fill the chunk completely unless we reach the end of the AD
+            let mut filled = 0;
+            while filled < buf.len() {
+                match ad.read(&mut buf[filled..]) {
+                    Ok(0) => break,
+                    Ok(read) => filled += read,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e)?
+                }
+            }
+            
+            // Process the chunk
+            poly1305_update(&mut a, &r, &u, &buf[..filled], false);
+            ad_len += filled as u64;
+            if filled < buf.len() { break }
+        }
+        
+        // Process data and the footer
+        let mut foot = Vec::with_capacity(16);
+        foot.extend_from_slice(&ad_len.to_le_bytes());
+        foot.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        poly1305_update(&mut a, &r, &u, data, false);
+        poly1305_update(&mut a, &r, &u, &foot, true);
+        poly1305_finish(tag, &mut a, &s);
+        Ok(())
+    }
+    
+    /// A helper function for the original ChachaPoly AEAD construction
+    pub(in crate) fn chachapoly_orig_auth(tag: &mut[u8], ad: &[u8], data: &[u8], key: &[u8]) {
+        // Assemble the authenticated data (`ad || ad_len || data || data_len` without padding)
+        let mut mac_data = Vec::with_capacity(ad.len() + data.len() + 16);
+        mac_data.extend_from_slice(ad);
+        mac_data.extend_from_slice(&(ad.len() as u64).to_le_bytes());
+        mac_data.extend_from_slice(data);
+        mac_data.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        
+        // Authenticate the data
+        let (mut r, mut s, mut u, mut a) = (vec![0; 5], vec![0; 4], vec![0; 5], vec![0; 5]);
+        poly1305_init(&mut r, &mut s, &mut u, key);
+        poly1305_update(&mut a, &r, &u, &mac_data, true);
+        poly1305_finish(tag, &mut a, &s);
+    }
 }
 impl SecKeyGen for Poly1305 {
     fn new_sec_key(&self, buf: &mut[u8], rng: &mut dyn SecureRng) -> Result<usize, Box<dyn Error + 'static>> {
@@ -54,15 +208,6 @@ impl Mac for Poly1305 {
     }
     
     fn auth(&self, buf: &mut[u8], data: &[u8], key: &[u8]) -> Result<usize, Box<dyn Error + 'static>> {
-        // Verify input
-        vfy_auth!(key => [POLY1305_KEY], => [buf, POLY1305_TAG]);
-        
-        // Authenticate data
-        let (mut r, mut s, mut u, mut a) = (vec![0; 5], vec![0; 4], vec![0; 5], vec![0; 5]);
-        poly1305_init(&mut r, &mut s, &mut u, key);
-        poly1305_update(&mut a, &r, &u, data, true);
-        poly1305_finish(buf, &mut a, &s);
-        
-        Ok(POLY1305_TAG)
+        Ok(Self::auth_raw(buf, data, key, true)?)
     }
 }
\ No newline at end of file
@@ -0,0 +1,121 @@
+use crate::core::{
+    chacha20::{ self, chacha20_rounds, hchacha20_hash },
+    poly1305::{ poly1305_init, poly1305_update, poly1305_finish }
+};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+
+/// Performs the 20 ChaCha20 rounds (10 double rounds) over `state` in place
+///
+/// This is the raw permutation without the final addition of the input state.
+pub fn chacha20_permute(state: &mut[u32; 16]) {
+    chacha20_rounds(state)
+}
+
+
+/// Computes the HChaCha20 subkey for `key` and the 16 byte `nonce`
+pub fn hchacha20(key: &[u8; 32], nonce: &[u8; 16]) -> [u8; 32] {
+    let mut subkey = [0; 32];
+    hchacha20_hash(key, nonce, &mut subkey);
+    subkey
+}
+
+
+/// Computes the `n`th ChaCha20-IETF keystream block for `key` and the 12 byte `nonce`
+///
+/// Example ([RFC 8439 section 2.3.2](https://tools.ietf.org/html/rfc8439#section-2.3.2)):
+/// ```
+/// use crypto_api_chachapoly::primitives::chacha20_ietf_block;
+///
+/// let mut key = [0; 32];
+/// key.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+/// let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+///
+/// let block = chacha20_ietf_block(&key, &nonce, 1);
+/// assert_eq!(block[..8], [0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15]);
+/// ```
+pub fn chacha20_ietf_block(key: &[u8; 32], nonce: &[u8; 12], n: u32) -> [u8; 64] {
+    let mut block = [0; 64];
+    chacha20::chacha20_ietf_block(key, nonce, n, &mut block);
+    block
+}
+
+
+/// Computes the `n`th original ChaCha20 keystream block for `key` and the 8 byte `nonce` (with a
+/// 64 bit block counter)
+pub fn chacha20_block(key: &[u8; 32], nonce: &[u8; 8], n: u64) -> [u8; 64] {
+    let mut block = [0; 64];
+    chacha20::chacha20_block(key, nonce, n, &mut block);
+    block
+}
+
+
+/// An incremental Poly1305 computation
+///
+/// Every `update` but the last must process a multiple of 16 bytes; the final incomplete block of
+/// the last `update` is padded as specified by `pad_final` (see `Poly1305::auth_raw`). If the
+/// `zeroize` feature is enabled, the key-derived state is erased from memory when it is dropped.
+///
+/// Example ([RFC 8439 section 2.5.2](https://tools.ietf.org/html/rfc8439#section-2.5.2)):
+/// ```
+/// use crypto_api_chachapoly::primitives::Poly1305State;
+///
+/// let key = [
+///     0x85, 0xd6, 0xbe, 0x78, 0x57, 0x55, 0x6d, 0x33, 0x7f, 0x44, 0x52, 0xfe, 0x42, 0xd5, 0x06,
+///     0xa8, 0x01, 0x03, 0x80, 0x8a, 0xfb, 0x0d, 0xb2, 0xfd, 0x4a, 0xbf, 0xf6, 0xaf, 0x41, 0x49,
+///     0xf5, 0x1b
+/// ];
+/// let mut state = Poly1305State::new(&key);
+/// state.update(b"Cryptographic Fo", false);
+/// state.update(b"rum Research Group", true);
+/// assert_eq!(state.finish(), [
+///     0xa8, 0x06, 0x1d, 0xc1, 0x30, 0x51, 0x36, 0xc6,
+///     0xc2, 0x2b, 0x8b, 0xaf, 0x0c, 0x01, 0x27, 0xa9
+/// ]);
+/// ```
+pub struct Poly1305State {
+    r: [u32; 5],
+    s: [u32; 4],
+    u: [u32; 5],
+    a: [u32; 5]
+}
+impl Poly1305State {
+    /// Creates a new state for the one-time `key`
+    pub fn new(key: &[u8; 32]) -> Self {
+        let (mut r, mut s, mut u) = ([0; 5], [0; 4], [0; 5]);
+        poly1305_init(&mut r, &mut s, &mut u, key);
+        Self{ r, s, u, a: [0; 5] }
+    }
+    
+    /// Processes `data`
+    ///
+    /// If `pad_final` is `true`, an incomplete final block is padded with `0x01` and `0x00` bytes
+    /// (standard Poly1305); otherwise it is padded with `0x00` bytes and processed like a complete
+    /// block (like the AD and ciphertext in ChachaPoly-IETF).
+    pub fn update(&mut self, data: &[u8], pad_final: bool) {
+        poly1305_update(&mut self.a, &self.r, &self.u, data, pad_final)
+    }
+    
+    /// Finalizes the computation and returns the tag
+    pub fn finish(mut self) -> [u8; 16] {
+        let mut tag = [0; 16];
+        poly1305_finish(&mut tag, &mut self.a, &self.s);
+        tag
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Zeroize for Poly1305State {
+    fn zeroize(&mut self) {
+        self.r.zeroize();
+        self.s.zeroize();
+        self.u.zeroize();
+        self.a.zeroize();
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Drop for Poly1305State {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,51 @@
+use crate::chachapoly_ietf::{ ChachaPolyIetf, CHACHAPOLY_KEY };
+use crypto_api::rng::{ SecureRng, SecKeyGen };
+use std::{ error::Error, ops::Deref };
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+
+/// A 256 bit secret key for all algorithms in this crate
+///
+/// If the `zeroize` feature is enabled, the key is erased from memory when it is dropped.
+pub struct SecretKey([u8; CHACHAPOLY_KEY]);
+impl SecretKey {
+    /// Generates a new random key using `rng`
+    pub fn generate(rng: &mut dyn SecureRng) -> Result<Self, Box<dyn Error + 'static>> {
+        let mut key = Self::zeroed();
+        ChachaPolyIetf.new_sec_key(&mut key.0, rng)?;
+        Ok(key)
+    }
+    
+    /// Creates an all-zero key that is filled in place (e.g. when unwrapping a key)
+    pub(in crate) fn zeroed() -> Self {
+        Self([0; CHACHAPOLY_KEY])
+    }
+    /// Gets the key bytes mutably
+    pub(in crate) fn as_mut_bytes(&mut self) -> &mut[u8] {
+        &mut self.0
+    }
+}
+impl Deref for SecretKey {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl AsRef<[u8]> for SecretKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Zeroize for SecretKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
@@ -0,0 +1,232 @@
+use crate::{
+    ChachaPolyError, Misuse,
+    chachapoly_ietf::{
+        CHACHAPOLY_KEY, CHACHAPOLY_NONCE, CHACHAPOLY_TAG, chachapoly_seal, chachapoly_open
+    }
+};
+use std::{
+    cmp::min,
+    io::{ self, Read, Write, ErrorKind }
+};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+
+/// The size of a plaintext chunk (64 KiB); each sealed chunk is `CHACHAPOLY_TAG` bytes larger
+pub const CHACHAPOLY_STREAM_CHUNK: usize = 64 * 1024;
+/// The size of the nonce prefix for the STREAM construction (56 bits/7 bytes)
+pub const CHACHAPOLY_STREAM_PREFIX: usize = 7;
+
+
+/// Converts `error` into an `io::Error`
+fn io_error(error: ChachaPolyError) -> io::Error {
+    match error {
+        ChachaPolyError::InvalidData | ChachaPolyError::TruncatedInput => {
+            io::Error::new(ErrorKind::InvalidData, error)
+        },
+        ChachaPolyError::ApiMisuse(_) => io::Error::new(ErrorKind::InvalidInput, error)
+    }
+}
+
+
+/// The shared state of the STREAM construction
+///
+/// If the `zeroize` feature is enabled, the key, nonce and counter are erased from memory when the
+/// state is dropped.
+struct StreamState {
+    key: [u8; CHACHAPOLY_KEY],
+    nonce: [u8; CHACHAPOLY_NONCE],
+    counter: u64,
+    finished: bool
+}
+impl StreamState {
+    /// Creates a new state for `key` and `nonce_prefix`
+    pub fn new(key: &[u8], nonce_prefix: &[u8]) -> Result<Self, ChachaPolyError> {
+        // Verify input
+        if key.len() != CHACHAPOLY_KEY {
+            let misuse = Misuse::KeyLen{ expected: CHACHAPOLY_KEY, got: key.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        if nonce_prefix.len() != CHACHAPOLY_STREAM_PREFIX {
+            let misuse = Misuse::NonceLen{
+                expected: CHACHAPOLY_STREAM_PREFIX, got: nonce_prefix.len()
+            };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Create the state
+        let (mut this_key, mut nonce) = ([0; CHACHAPOLY_KEY], [0; CHACHAPOLY_NONCE]);
+        this_key.copy_from_slice(key);
+        nonce[..CHACHAPOLY_STREAM_PREFIX].copy_from_slice(nonce_prefix);
+        Ok(Self{ key: this_key, nonce, counter: 0, finished: false })
+    }
+    
+    /// Computes the nonce for the next chunk (`prefix || be32(counter) || last`)
+    pub fn nonce(&self, last: bool) -> Result<[u8; CHACHAPOLY_NONCE], ChachaPolyError> {
+        // Ensure that we neither reuse a nonce nor continue a finished stream
+        if self.finished || self.counter > u32::MAX as u64 {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        
+        // Assemble the nonce
+        let mut nonce = self.nonce;
+        nonce[CHACHAPOLY_STREAM_PREFIX..11].copy_from_slice(&(self.counter as u32).to_be_bytes());
+        nonce[11] = last as u8;
+        Ok(nonce)
+    }
+    /// Advances the state after a chunk has been processed successfully
+    pub fn advance(&mut self, last: bool) {
+        self.counter += 1;
+        self.finished = last;
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Drop for StreamState {
+    /// Erases the state
+    ///
+    /// _Note: The chunk buffers of `ChachaPolyWriter`/`ChachaPolyReader` are not part of the state
+    /// and are thus not erased; they may contain plaintext from the last chunk._
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.nonce.zeroize();
+        self.counter.zeroize();
+    }
+}
+
+
+/// A writer that encrypts all data written to it into `W` using the
+/// [STREAM construction](https://eprint.iacr.org/2015/189.pdf) over ChachaPoly-IETF
+///
+/// The plaintext is split into chunks of `CHACHAPOLY_STREAM_CHUNK` bytes which are sealed
+/// separately with the nonce `nonce_prefix || be32(chunk_index) || last_flag`; the last chunk may
+/// be shorter (or even empty). Because the last chunk is only known at the end, you __must__ call
+/// `finish` to write it – otherwise the stream is considered truncated by `ChachaPolyReader`.
+///
+/// _Note: `flush` only flushes the underlying writer; it cannot write a partial chunk._
+pub struct ChachaPolyWriter<W: Write> {
+    inner: W,
+    state: StreamState,
+    buf: Vec<u8>
+}
+impl<W: Write> ChachaPolyWriter<W> {
+    /// Creates a new writer that encrypts into `inner` using `key` and a 7 byte `nonce_prefix`
+    ///
+    /// _Note: The `key`/`nonce_prefix` combination must never be reused_
+    pub fn new(inner: W, key: &[u8], nonce_prefix: &[u8]) -> Result<Self, ChachaPolyError> {
+        let state = StreamState::new(key, nonce_prefix)?;
+        Ok(Self{ inner, state, buf: Vec::with_capacity(CHACHAPOLY_STREAM_CHUNK) })
+    }
+    
+    /// Seals and writes the final chunk and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+    
+    /// Seals and writes the next chunk
+    fn write_chunk(&mut self, last: bool) -> io::Result<()> {
+        // Seal the chunk
+        let nonce = self.state.nonce(last).map_err(io_error)?;
+        let len = min(self.buf.len(), CHACHAPOLY_STREAM_CHUNK);
+        let mut chunk = vec![0; len + CHACHAPOLY_TAG];
+        let (data, tag) = chunk.split_at_mut(len);
+        data.copy_from_slice(&self.buf[..len]);
+        chachapoly_seal(data, tag, &[], &self.state.key, &nonce);
+        
+        // Write the chunk
+        self.inner.write_all(&chunk)?;
+        self.buf.drain(..len);
+        self.state.advance(last);
+        Ok(())
+    }
+}
+impl<W: Write> Write for ChachaPolyWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // Only write a chunk if there is more data so that the last chunk is never written here
+        self.buf.extend_from_slice(data);
+        while self.buf.len() > CHACHAPOLY_STREAM_CHUNK {
+            self.write_chunk(false)?;
+        }
+        Ok(data.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+
+/// A reader that decrypts and validates the data read from `R` that has been written by a
+/// `ChachaPolyWriter`
+///
+/// Each chunk is validated before any of its plaintext is returned. If the stream is truncated
+/// (i.e. the final chunk is missing) or has been tampered with, `read` fails with
+/// `io::ErrorKind::InvalidData`.
+pub struct ChachaPolyReader<R: Read> {
+    inner: R,
+    state: StreamState,
+    sealed: Vec<u8>,
+    plaintext: Vec<u8>,
+    pos: usize
+}
+impl<R: Read> ChachaPolyReader<R> {
+    /// Creates a new reader that decrypts `inner` using `key` and a 7 byte `nonce_prefix`
+    pub fn new(inner: R, key: &[u8], nonce_prefix: &[u8]) -> Result<Self, ChachaPolyError> {
+        let state = StreamState::new(key, nonce_prefix)?;
+        Ok(Self{ inner, state, sealed: Vec::new(), plaintext: Vec::new(), pos: 0 })
+    }
+    
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    
+    /// Reads, validates and decrypts the next chunk
+    fn read_chunk(&mut self) -> io::Result<()> {
+        // Read a sealed chunk and one more byte to see if this is the last chunk
+        let wanted = CHACHAPOLY_STREAM_CHUNK + CHACHAPOLY_TAG + 1;
+        while self.sealed.len() < wanted {
+            let filled = self.sealed.len();
+            self.sealed.resize(wanted, 0);
+            let read = self.inner.read(&mut self.sealed[filled..]);
+            self.sealed.truncate(filled + *read.as_ref().unwrap_or(&0));
+            match read {
+                Ok(0) => break,
+                Err(e) if e.kind() != ErrorKind::Interrupted => Err(e)?,
+                _ => continue
+            }
+        }
+        
+        // Validate and decrypt the chunk (a chunk shorter than the tag means that it is truncated)
+        let last = self.sealed.len() < wanted;
+        let len = min(self.sealed.len(), CHACHAPOLY_STREAM_CHUNK + CHACHAPOLY_TAG);
+        if len < CHACHAPOLY_TAG { Err(io_error(ChachaPolyError::TruncatedInput))? }
+        
+        let nonce = self.state.nonce(last).map_err(io_error)?;
+        let mut chunk = self.sealed[..len].to_vec();
+        let (data, tag) = chunk.split_at_mut(len - CHACHAPOLY_TAG);
+        chachapoly_open(data, tag, &[], &self.state.key, &nonce).map_err(io_error)?;
+        
+        // Store the plaintext and advance the state
+        self.sealed.drain(..len);
+        self.state.advance(last);
+        chunk.truncate(len - CHACHAPOLY_TAG);
+        self.plaintext = chunk;
+        self.pos = 0;
+        Ok(())
+    }
+}
+impl<R: Read> Read for ChachaPolyReader<R> {
+    fn read(&mut self, buf: &mut[u8]) -> io::Result<usize> {
+        // Read the next chunk(s) if necessary
+        while self.pos == self.plaintext.len() && !self.state.finished {
+            self.read_chunk()?;
+        }
+        
+        // Copy the plaintext
+        let to_copy = min(buf.len(), self.plaintext.len() - self.pos);
+        buf[..to_copy].copy_from_slice(&self.plaintext[self.pos..self.pos + to_copy]);
+        self.pos += to_copy;
+        Ok(to_copy)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,68 @@
+use crate::chachapoly_ietf::CHACHAPOLY_TAG;
+use std::ops::Deref;
+#[cfg(feature = "subtle")]
+use subtle::ConstantTimeEq;
+
+
+/// Compares the received `tag` with the recomputed `vfy_tag` in constant time
+///
+/// If the `subtle` feature is enabled, the comparison uses `subtle::ConstantTimeEq` instead of the
+/// built-in `eq_ct!` macro.
+///
+/// If the `fault-resistant` feature is enabled, the tags are compared a second time with an
+/// independent routine (different operations and the reverse order) and are only considered equal
+/// if both comparisons agree; a single fault that flips or skips one comparison therefore cannot
+/// make an invalid tag pass.
+pub(in crate) fn verify_tag(tag: &[u8], vfy_tag: &[u8]) -> bool {
+    #[cfg(not(feature = "subtle"))]
+    let is_eq = eq_ct!(tag, vfy_tag);
+    #[cfg(feature = "subtle")]
+    let is_eq: bool = tag.ct_eq(vfy_tag).into();
+    
+    #[cfg(feature = "fault-resistant")]
+    let is_eq = {
+        // Accumulate the differences via subtraction in reverse order
+        let mut diff = (tag.len() != vfy_tag.len()) as u32;
+        for i in (0..tag.len().min(vfy_tag.len())).rev() {
+            diff |= (tag[i] as u32).wrapping_sub(vfy_tag[i] as u32);
+        }
+        is_eq & (diff == 0)
+    };
+    is_eq
+}
+
+/// A 128 bit authentication tag
+///
+/// The equality of two tags is checked in constant time, so `Tag(a) == Tag(b)` is safe to use to
+/// verify a tag (unlike `a == b` on the raw byte arrays which may return early on the first
+/// mismatch). If the `subtle` feature is enabled, the tags are compared with
+/// `subtle::ConstantTimeEq`.
+#[derive(Debug, Copy, Clone)]
+pub struct Tag(pub [u8; CHACHAPOLY_TAG]);
+impl PartialEq for Tag {
+    #[cfg(not(feature = "subtle"))]
+    fn eq(&self, other: &Self) -> bool {
+        eq_ct!(&self.0, &other.0)
+    }
+    #[cfg(feature = "subtle")]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+}
+impl Eq for Tag {}
+impl From<[u8; CHACHAPOLY_TAG]> for Tag {
+    fn from(tag: [u8; CHACHAPOLY_TAG]) -> Self {
+        Self(tag)
+    }
+}
+impl Deref for Tag {
+    type Target = [u8; CHACHAPOLY_TAG];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl AsRef<[u8]> for Tag {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+/// The name of the counter that is incremented for every successful seal
+pub const SEAL_COUNTER: &str = "chachapoly_seals_total";
+/// The name of the counter that is incremented for every successful open
+pub const OPEN_COUNTER: &str = "chachapoly_opens_total";
+/// The name of the counter that is incremented for every open that failed due to an invalid tag
+pub const AUTH_FAILURE_COUNTER: &str = "chachapoly_auth_failures_total";
+
+
+/// Counts a successful seal for `algorithm`
+///
+/// This is a no-op if the `metrics` feature is disabled
+#[inline(always)]
+pub(in crate) fn count_seal(algorithm: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(SEAL_COUNTER, "algorithm" => algorithm).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = algorithm;
+}
+/// Counts a successful open for `algorithm`
+///
+/// This is a no-op if the `metrics` feature is disabled
+#[inline(always)]
+pub(in crate) fn count_open(algorithm: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(OPEN_COUNTER, "algorithm" => algorithm).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = algorithm;
+}
+/// Counts an open that failed due to an invalid tag for `algorithm`
+///
+/// This is a no-op if the `metrics` feature is disabled
+#[inline(always)]
+pub(in crate) fn count_auth_failure(algorithm: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(AUTH_FAILURE_COUNTER, "algorithm" => algorithm).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = algorithm;
+}
\ No newline at end of file
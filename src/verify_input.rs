@@ -20,15 +20,33 @@ impl<T: AsRef<[u8]>> SliceExt for T {
 }
 
 
+/// Splits `buf` into the first `data_len` bytes and the following `tag_len` bytes
+///
+/// _Note: This is a last line of defense in case a length check has been bypassed; it returns a
+/// `Misuse::BufferTooSmall` error instead of panicking if `buf` is too small_
+pub fn split_tag_mut(buf: &mut[u8], data_len: usize, tag_len: usize)
+    -> Result<(&mut[u8], &mut[u8]), crate::ChachaPolyError>
+{
+    let needed = data_len.saturating_add(tag_len);
+    if buf.len() < needed {
+        let misuse = crate::Misuse::BufferTooSmall{ needed, got: buf.len() };
+        Err(crate::ChachaPolyError::ApiMisuse(misuse))?
+    }
+    
+    let (data, tag) = buf.split_at_mut(data_len);
+    Ok((data, &mut tag[..tag_len]))
+}
+
+
 /// Verifies that
 ///  - `$buf` is can hold *exactly* `$size` bytes
 macro_rules! vfy_keygen {
     ($size:expr => $buf:expr) => ({
         #[allow(unused_imports)]
-        use $crate::verify_input::{ UsizeExt, SliceExt };
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
         
         let error = match true {
-            _ if $buf._cv() != $size => Err("Invalid buffer size"),
+            _ if $buf._cv() != $size => Err(Misuse::BufferLen),
             _ => Ok(())
         };
         error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
@@ -42,13 +60,19 @@ macro_rules! vfy_enc {
         $plaintext:expr => [$buf:expr, $plaintext_limit:expr]) =>
     ({
         #[allow(unused_imports)]
-        use $crate::verify_input::{ UsizeExt, SliceExt };
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
         
         let error = match true {
-            _ if $key._cv() != $key_size => Err("Invalid key length"),
-            _ if $nonce._cv() != $nonce_size => Err("Invalid nonce length"),
-            _ if $plaintext._cv() > $plaintext_limit => Err("Too much data"),
-            _ if $plaintext._cv() > $buf._cv() => Err("Buffer is too small"),
+            _ if $key._cv() != $key_size => Err(Misuse::KeyLen{
+                expected: $key_size, got: $key._cv()
+            }),
+            _ if $nonce._cv() != $nonce_size => Err(Misuse::NonceLen{
+                expected: $nonce_size, got: $nonce._cv()
+            }),
+            _ if $plaintext._cv() > $plaintext_limit => Err(Misuse::TooMuchData),
+            _ if $plaintext._cv() > $buf._cv() => Err(Misuse::BufferTooSmall{
+                needed: $plaintext._cv(), got: $buf._cv()
+            }),
             _ => Ok(())
         };
         error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
@@ -61,13 +85,19 @@ macro_rules! vfy_dec {
         $ciphertext:expr => [$buf:expr, $ciphertext_limit:expr]) =>
     ({
         #[allow(unused_imports)]
-        use $crate::verify_input::{ UsizeExt, SliceExt };
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
         
         let error = match true {
-            _ if $key._cv() != $key_size => Err("Invalid key length"),
-            _ if $nonce._cv() != $nonce_size => Err("Invalid nonce length"),
-            _ if $ciphertext._cv() > $ciphertext_limit => Err("Too much data"),
-            _ if $ciphertext._cv() > $buf._cv() => Err("Buffer is too small"),
+            _ if $key._cv() != $key_size => Err(Misuse::KeyLen{
+                expected: $key_size, got: $key._cv()
+            }),
+            _ if $nonce._cv() != $nonce_size => Err(Misuse::NonceLen{
+                expected: $nonce_size, got: $nonce._cv()
+            }),
+            _ if $ciphertext._cv() > $ciphertext_limit => Err(Misuse::TooMuchData),
+            _ if $ciphertext._cv() > $buf._cv() => Err(Misuse::BufferTooSmall{
+                needed: $ciphertext._cv(), got: $buf._cv()
+            }),
             _ => Ok(())
         };
         error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
@@ -79,11 +109,15 @@ macro_rules! vfy_dec {
 macro_rules! vfy_auth {
     ($key:expr => [$key_size:expr], => [$buf:expr, $tag_size:expr]) => ({
         #[allow(unused_imports)]
-        use $crate::verify_input::{ UsizeExt, SliceExt };
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
         
         let error = match true {
-            _ if $key._cv() != $key_size => Err("Invalid key length"),
-            _ if $buf._cv() < $tag_size => Err("Buffer is too small"),
+            _ if $key._cv() != $key_size => Err(Misuse::KeyLen{
+                expected: $key_size, got: $key._cv()
+            }),
+            _ if $buf._cv() < $tag_size => Err(Misuse::BufferTooSmall{
+                needed: $tag_size, got: $buf._cv()
+            }),
             _ => Ok(())
         };
         error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
@@ -92,37 +126,87 @@ macro_rules! vfy_auth {
 
 
 /// Verifies the sealing parameters
+///
+/// _Note: The required buffer size is computed with a saturating addition to avoid an overflow if
+/// `$plaintext` is near `usize::MAX`_
 macro_rules! vfy_seal {
     ($key:expr => [$key_size:expr], $nonce:expr => [$nonce_const:expr],
         $plaintext:expr => [$buf:expr, $plaintext_limit:expr]) =>
     ({
         #[allow(unused_imports)]
-        use $crate::verify_input::{ UsizeExt, SliceExt };
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
         
         let error = match true {
-            _ if $key._cv() != $key_size => Err("Invalid key length"),
-            _ if $nonce._cv() != $nonce_const => Err("Invalid nonce length"),
-            _ if $plaintext._cv() > $plaintext_limit => Err("Too much data"),
-            _ if $buf._cv() < $plaintext._cv() + CHACHAPOLY_TAG => Err("Buffer is too small"),
+            _ if $key._cv() != $key_size => Err(Misuse::KeyLen{
+                expected: $key_size, got: $key._cv()
+            }),
+            _ if $nonce._cv() != $nonce_const => Err(Misuse::NonceLen{
+                expected: $nonce_const, got: $nonce._cv()
+            }),
+            _ if $plaintext._cv() > $plaintext_limit => Err(Misuse::TooMuchData),
+            _ if $buf._cv() < $plaintext._cv().saturating_add(CHACHAPOLY_TAG) => {
+                Err(Misuse::BufferTooSmall{
+                    needed: $plaintext._cv().saturating_add(CHACHAPOLY_TAG), got: $buf._cv()
+                })
+            },
             _ => Ok(())
         };
         error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
     })
 }
 /// Verifies the parameters for opening in place
+///
+/// _Note: The buffer size is checked by subtraction to avoid an overflow if `$buf` is near
+/// `usize::MAX`; this is safe because `$ciphertext` is known to be at least `$tag_size` bytes_
 macro_rules! vfy_open {
     ($key:expr => [$key_size:expr], $nonce:expr => [$nonce_size:expr],
         $ciphertext:expr => [$buf:expr, $tag_size:expr, $ciphertext_limit:expr]) =>
     ({
         #[allow(unused_imports)]
-        use $crate::verify_input::{ UsizeExt, SliceExt };
-    
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
+        
+        let error = match true {
+            _ if $key._cv() != $key_size => Err(Misuse::KeyLen{
+                expected: $key_size, got: $key._cv()
+            }),
+            _ if $nonce._cv() != $nonce_size => Err(Misuse::NonceLen{
+                expected: $nonce_size, got: $nonce._cv()
+            }),
+            _ if $ciphertext._cv() > $ciphertext_limit => Err(Misuse::TooMuchData),
+            _ if $ciphertext._cv() < $tag_size => Err($crate::ChachaPolyError::TruncatedInput)?,
+            _ if $buf._cv() < $ciphertext._cv() - $tag_size => Err(Misuse::BufferTooSmall{
+                needed: $ciphertext._cv() - $tag_size, got: $buf._cv()
+            }),
+            _ => Ok(())
+        };
+        error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
+    })
+}
+/// Verifies the parameters for opening in place
+///
+/// _Note: Unlike `vfy_open!`, this requires `$buf` to hold the entire `$ciphertext_len` bytes
+/// (including the tag) because the ciphertext is read from `$buf` itself_
+macro_rules! vfy_open_in_place {
+    ($key:expr => [$key_size:expr], $nonce:expr => [$nonce_size:expr],
+        $ciphertext_len:expr => [$buf:expr, $tag_size:expr, $ciphertext_limit:expr]) =>
+    ({
+        #[allow(unused_imports)]
+        use $crate::{ Misuse, verify_input::{ UsizeExt, SliceExt } };
+        
         let error = match true {
-            _ if $key._cv() != $key_size => Err("Invalid key length"),
-            _ if $nonce._cv() != $nonce_size => Err("Invalid nonce length"),
-            _ if $ciphertext._cv() > $ciphertext_limit => Err("Too much data"),
-            _ if $ciphertext._cv() < $tag_size => Err($crate::ChachaPolyError::InvalidData)?,
-            _ if $buf._cv() + $tag_size < $ciphertext._cv() => Err("Buffer is too small"),
+            _ if $key._cv() != $key_size => Err(Misuse::KeyLen{
+                expected: $key_size, got: $key._cv()
+            }),
+            _ if $nonce._cv() != $nonce_size => Err(Misuse::NonceLen{
+                expected: $nonce_size, got: $nonce._cv()
+            }),
+            _ if $ciphertext_len._cv() > $ciphertext_limit => Err(Misuse::TooMuchData),
+            _ if $ciphertext_len._cv() < $tag_size => {
+                Err($crate::ChachaPolyError::TruncatedInput)?
+            },
+            _ if $buf._cv() < $ciphertext_len._cv() => Err(Misuse::BufferTooSmall{
+                needed: $ciphertext_len._cv(), got: $buf._cv()
+            }),
             _ => Ok(())
         };
         error.map_err(|e| $crate::ChachaPolyError::ApiMisuse(e))?;
@@ -0,0 +1,119 @@
+use crate::{
+    ChachaPolyError, Misuse, ChachaPolyIetf, XChachaPoly,
+    chachapoly_ietf::{ CHACHAPOLY_NONCE, CHACHAPOLY_TAG }, xchachapoly::XCHACHAPOLY_NONCE
+};
+use crypto_api::cipher::AeadCipher;
+use std::error::Error;
+
+
+/// The algorithm of a `VersionedAead` message
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum AeadAlgorithm {
+    /// ChachaPoly-IETF (identifier `0x01`)
+    ChachaPolyIetf,
+    /// XChachaPoly (identifier `0x02`)
+    XChachaPoly
+}
+impl AeadAlgorithm {
+    /// Gets the algorithm for the identifier `id` or `ApiMisuse(UnknownAlgorithm)` if `id` is
+    /// unknown
+    pub fn from_id(id: u8) -> Result<Self, ChachaPolyError> {
+        match id {
+            0x01 => Ok(AeadAlgorithm::ChachaPolyIetf),
+            0x02 => Ok(AeadAlgorithm::XChachaPoly),
+            _ => Err(ChachaPolyError::ApiMisuse(Misuse::UnknownAlgorithm(id)))
+        }
+    }
+    /// The 1 byte identifier of the algorithm
+    pub const fn id(self) -> u8 {
+        match self {
+            AeadAlgorithm::ChachaPolyIetf => 0x01,
+            AeadAlgorithm::XChachaPoly => 0x02
+        }
+    }
+    /// The nonce length of the algorithm
+    pub const fn nonce_len(self) -> usize {
+        match self {
+            AeadAlgorithm::ChachaPolyIetf => CHACHAPOLY_NONCE,
+            AeadAlgorithm::XChachaPoly => XCHACHAPOLY_NONCE
+        }
+    }
+    /// Creates a new `AeadCipher` instance for the algorithm
+    pub fn aead_cipher(self) -> Box<dyn AeadCipher> {
+        match self {
+            AeadAlgorithm::ChachaPolyIetf => ChachaPolyIetf::aead_cipher(),
+            AeadAlgorithm::XChachaPoly => XChachaPoly::aead_cipher()
+        }
+    }
+}
+
+
+/// A self-describing AEAD format (`algorithm_id || nonce || ciphertext || tag`) for crypto agility
+///
+/// Messages are sealed with the configured algorithm, but `open` dispatches on the algorithm
+/// identifier of each message; this allows a deployment to migrate to another algorithm without a
+/// flag day.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct VersionedAead {
+    algorithm: AeadAlgorithm
+}
+impl VersionedAead {
+    /// Creates a new instance that seals with `algorithm`
+    pub const fn new(algorithm: AeadAlgorithm) -> Self {
+        Self{ algorithm }
+    }
+    /// The algorithm used to seal messages
+    pub const fn algorithm(&self) -> AeadAlgorithm {
+        self.algorithm
+    }
+    
+    /// Seals `plaintext` with `ad` and returns `algorithm_id || nonce || ciphertext || tag`
+    ///
+    /// Returns `ApiMisuse(NonceLen)` if `nonce` does not match the nonce length of the algorithm.
+    pub fn seal(&self, plaintext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+        -> Result<Vec<u8>, Box<dyn Error + 'static>>
+    {
+        // Verify input
+        if nonce.len() != self.algorithm.nonce_len() {
+            let misuse = Misuse::NonceLen{ expected: self.algorithm.nonce_len(), got: nonce.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Write the header
+        let mut message = vec![self.algorithm.id()];
+        message.extend_from_slice(nonce);
+        let header_len = message.len();
+        
+        // Seal the plaintext behind the header
+        message.resize(header_len + plaintext.len() + CHACHAPOLY_TAG, 0);
+        let len = self.algorithm.aead_cipher()
+            .seal_to(&mut message[header_len..], plaintext, ad, key, nonce)?;
+        message.truncate(header_len + len);
+        Ok(message)
+    }
+    /// Opens `message` (`algorithm_id || nonce || ciphertext || tag`) with `ad` using the algorithm
+    /// specified by the message and returns the plaintext
+    ///
+    /// Returns `ApiMisuse(UnknownAlgorithm)` if the algorithm identifier is unknown and
+    /// `TruncatedInput` if `message` is too short to contain the identifier, the nonce and the tag.
+    pub fn open(&self, message: &[u8], ad: &[u8], key: &[u8])
+        -> Result<Vec<u8>, Box<dyn Error + 'static>>
+    {
+        // Parse the header
+        let (&id, rest) = message.split_first().ok_or(ChachaPolyError::TruncatedInput)?;
+        let algorithm = AeadAlgorithm::from_id(id)?;
+        if rest.len() < algorithm.nonce_len() {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        let (nonce, ciphertext) = rest.split_at(algorithm.nonce_len());
+        if ciphertext.len() < CHACHAPOLY_TAG {
+            Err(ChachaPolyError::TruncatedInput)?
+        }
+        
+        // Open the ciphertext
+        let mut buf = vec![0; ciphertext.len() - CHACHAPOLY_TAG];
+        let len = algorithm.aead_cipher().open_to(&mut buf, ciphertext, ad, key, nonce)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
\ No newline at end of file
@@ -1,12 +1,15 @@
 use crate::{
+    ChaCha20, ChachaPolyError, Misuse, SecretKey,
     chacha20_ietf::CHACHA20_KEY,
-    core::chacha20::{ hchacha20_hash, chacha20_block }
+    core::chacha20::hchacha20_hash
 };
 use crypto_api::{
     cipher::{ CipherInfo, Cipher },
     rng::{ SecureRng, SecKeyGen }
 };
-use std::{ cmp::min, error::Error };
+use std::error::Error;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 
 /// The maximum amount of bytes that can be processed by this implementation with one key/nonce
@@ -27,6 +30,32 @@ impl XChaCha20 {
         Box::new(Self)
     }
     
+    /// Encrypts `buf` entirely in place with `key` and `nonce`
+    ///
+    /// This is equivalent to `Cipher::encrypt` with `plaintext_len = buf.len()`, but without the
+    /// length parameter (and without the need to box the error).
+    pub fn encrypt_in_place(buf: &mut[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [XCHACHA20_KEY], nonce => [XCHACHA20_NONCE],
+            buf => [buf, XCHACHA20_MAX]
+        );
+        
+        // Encrypt the data
+        Self::xor(key, nonce, 0, buf);
+        Ok(())
+    }
+    /// Decrypts `buf` entirely in place with `key` and `nonce`
+    ///
+    /// This is equivalent to `Cipher::decrypt` with `ciphertext_len = buf.len()`.
+    pub fn decrypt_in_place(buf: &mut[u8], key: &[u8], nonce: &[u8])
+        -> Result<(), ChachaPolyError>
+    {
+        Self::encrypt_in_place(buf, key, nonce)
+    }
+    
     /// XORs the bytes in `data` with the XChaCha20 keystream for `key` and `nonce` starting at the
     /// `n`th block
     ///
@@ -37,28 +66,16 @@ impl XChaCha20 {
     ///  - `n` exceeds `2^64 - 1` (which means that `data` must be smaller than `(2^64 - n) * 64`)
     ///
     /// __Consider using the `crypto_api`-interface instead of calling this function directly__
-    pub fn xor(key: &[u8], nonce: &[u8], mut n: u64, mut data: &mut[u8]) {
+    pub fn xor(key: &[u8], nonce: &[u8], n: u64, data: &mut[u8]) {
         // Verify input
         assert_eq!(XCHACHA20_KEY, key.len());
         assert_eq!(XCHACHA20_NONCE, nonce.len());
         
-        // Derive key
+        // Derive key and XOR `data`
         let (x_nonce, nonce) = nonce.split_at(16);
-        let mut x_key = vec![0; 32];
-        hchacha20_hash(key, x_nonce, &mut x_key);
-        
-        // XOR `data`
-        let mut buf = vec![0; 64];
-        while !data.is_empty() {
-            // Compute next block
-            chacha20_block(&x_key, nonce, n, &mut buf);
-            n = n.checked_add(1).expect("The ChaCha20 block counter must not exceed 2^64 - 1");
-            
-            // Xor block
-            let to_xor = min(data.len(), buf.len());
-            (0..to_xor).for_each(|i| data[i] = xor!(data[i], buf[i]));
-            data = &mut data[to_xor..];
-        }
+        let mut x_key = SecretKey::zeroed();
+        hchacha20_hash(key, x_nonce, x_key.as_mut_bytes());
+        ChaCha20::xor(&x_key, nonce, n, data);
     }
 }
 impl SecKeyGen for XChaCha20 {
@@ -141,3 +158,52 @@ impl Cipher for XChaCha20 {
         Ok(ciphertext.len())
     }
 }
+
+
+/// An XChaCha20 session for many messages whose nonces share the same 16 byte prefix
+///
+/// The HChaCha20 subkey for `key` and the nonce prefix is derived once in `new` and reused for
+/// every `xor` call, which saves one HChaCha20 invocation per message. If the `zeroize` feature is
+/// enabled, the subkey is erased from memory when the session is dropped.
+pub struct XChaCha20Session {
+    x_key: [u8; XCHACHA20_KEY]
+}
+impl XChaCha20Session {
+    /// Creates a new session for `key` and the first 16 bytes of the nonces (`nonce_prefix`)
+    pub fn new(key: &[u8], nonce_prefix: &[u8; 16]) -> Result<Self, ChachaPolyError> {
+        // Verify input
+        if key.len() != XCHACHA20_KEY {
+            let misuse = Misuse::KeyLen{ expected: XCHACHA20_KEY, got: key.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Derive the subkey
+        let mut x_key = [0; XCHACHA20_KEY];
+        hchacha20_hash(key, nonce_prefix, &mut x_key);
+        Ok(Self{ x_key })
+    }
+    
+    /// XORs the bytes in `data` with the XChaCha20 keystream for the nonce
+    /// `nonce_prefix || nonce_suffix` starting at the `n`th block
+    ///
+    /// This is equivalent to `XChaCha20::xor` with the full 24 byte nonce.
+    ///
+    /// ## Warning:
+    /// This function panics if `n` exceeds `2^64 - 1` (which means that `data` must be smaller than
+    /// `(2^64 - n) * 64`)
+    pub fn xor(&self, nonce_suffix: &[u8; 8], n: u64, data: &mut[u8]) {
+        ChaCha20::xor(&self.x_key, nonce_suffix, n, data)
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Zeroize for XChaCha20Session {
+    fn zeroize(&mut self) {
+        self.x_key.zeroize()
+    }
+}
+#[cfg(feature = "zeroize")]
+impl Drop for XChaCha20Session {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
\ No newline at end of file
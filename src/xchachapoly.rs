@@ -1,6 +1,9 @@
 use crate::{
-    ChachaPolyError, XChaCha20, Poly1305,
-    chachapoly_ietf::{ CHACHAPOLY_MAX, CHACHAPOLY_KEY, CHACHAPOLY_TAG }
+    ChachaPolyError, Misuse, ChaCha20, Poly1305, SecretKey, Tag,
+    core::chacha20::hchacha20_hash,
+    chachapoly_ietf::{ CHACHAPOLY_MAX, CHACHAPOLY_KEY, CHACHAPOLY_TAG },
+    telemetry::{ count_seal, count_open, count_auth_failure },
+    nonce::{ Endian, nonce_from_counter }, tag::verify_tag, verify_input::split_tag_mut
 };
 use crypto_api::{
     cipher::{ CipherInfo, Cipher, AeadCipher },
@@ -21,10 +24,22 @@ pub const XCHACHAPOLY_NONCE: usize = 24;
 pub const XCHACHAPOLY_TAG: usize = CHACHAPOLY_TAG;
 
 
+/// Derives the XChaCha20 subkey from `key` and the first 16 bytes of `nonce` and returns it
+/// together with the remaining 8 byte ChaCha20 nonce
+///
+/// The subkey is held in a `SecretKey`, so it is erased when it is dropped if the `zeroize`
+/// feature is enabled.
+fn xchacha20_subkey<'a>(key: &[u8], nonce: &'a[u8]) -> (SecretKey, &'a[u8]) {
+    let (x_nonce, nonce) = nonce.split_at(16);
+    let mut x_key = SecretKey::zeroed();
+    hchacha20_hash(key, x_nonce, x_key.as_mut_bytes());
+    (x_key, nonce)
+}
 /// Encrypts `data` in place and authenticates it with `ad` into `tag` using `key` and `nonce`
 fn xchachapoly_seal(data: &mut[u8], tag: &mut[u8], ad: &[u8], key: &[u8], nonce: &[u8]) {
-    // Encrypt the data
-    XChaCha20::xor(key, nonce, 1, data);
+    // Derive the subkey once and encrypt the data
+    let (x_key, nonce) = xchacha20_subkey(key, nonce);
+    ChaCha20::xor(&x_key, nonce, 1, data);
     
     // Create the footer
     let mut foot = Vec::with_capacity(16);
@@ -33,13 +48,17 @@ fn xchachapoly_seal(data: &mut[u8], tag: &mut[u8], ad: &[u8], key: &[u8], nonce:
     
     // Compute the Poly1305 key and the authentication tag
     let mut pkey = vec![0; 32];
-    XChaCha20::xor(key, nonce, 0, &mut pkey);
+    ChaCha20::xor(&x_key, nonce, 0, &mut pkey);
     Poly1305::chachapoly_auth(tag, ad, data, &foot, &pkey);
+    count_seal("XChachaPoly");
 }
 /// Validates `data` with `ad` and decrypts it in place using `key` and `nonce`
 fn xchachapoly_open(data: &mut[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
-    -> Result<(), Box<dyn Error + 'static>>
+    -> Result<(), ChachaPolyError>
 {
+    // Derive the subkey once
+    let (x_key, nonce) = xchacha20_subkey(key, nonce);
+    
     // Create the footer
     let mut foot = Vec::with_capacity(16);
     foot.extend_from_slice(&(ad.len() as u64).to_le_bytes());
@@ -47,18 +66,46 @@ fn xchachapoly_open(data: &mut[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[
     
     // Compute the Poly1305 key and the authentication tag
     let (mut pkey, mut vfy_tag) = (vec![0; 32], vec![0; 16]);
-    XChaCha20::xor(key, nonce, 0, &mut pkey);
+    ChaCha20::xor(&x_key, nonce, 0, &mut pkey);
     Poly1305::chachapoly_auth(&mut vfy_tag, ad, data, &foot, &pkey);
     
     // Validate the recomputed and the original tag
-    Ok(match eq_ct!(&tag, &vfy_tag) {
-        true => XChaCha20::xor(key, nonce, 1, data),
-        false => Err(ChachaPolyError::InvalidData)?
+    Ok(match verify_tag(tag, &vfy_tag) {
+        true => {
+            ChaCha20::xor(&x_key, nonce, 1, data);
+            count_open("XChachaPoly");
+        },
+        false => {
+            count_auth_failure("XChachaPoly");
+            Err(ChachaPolyError::InvalidData)?
+        }
     })
 }
+/// Copies `data` into `buf`, validates it with `ad` and decrypts it in place using `key` and `nonce`
+///
+/// Only `buf[..data.len()]` is written to; if the validation fails, these bytes are erased again
+fn xchachapoly_open_to(buf: &mut[u8], data: &[u8], tag: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
+    -> Result<(), ChachaPolyError>
+{
+    // Copy the data into buf and decrypt in place
+    let buf = &mut buf[..data.len()];
+    buf.copy_from_slice(data);
+    match xchachapoly_open(buf, tag, ad, key, nonce) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            buf.iter_mut().for_each(|b| *b = 0);
+            Err(e)
+        }
+    }
+}
 
 
 /// An implementation of XChaChaPoly
+///
+/// ## Output buffers
+/// All `*_to`-functions only write to the first `n` bytes of `buf` where `n` is the output length
+/// (which is returned on success); the remaining bytes of `buf` are never touched. If opening
+/// fails, the first `n` bytes are erased again so that `buf` never contains unauthenticated data.
 pub struct XChachaPoly;
 impl XChachaPoly {
     /// Creates a `Cipher` instance with `XChachaPolyIetf` as underlying cipher
@@ -69,6 +116,141 @@ impl XChachaPoly {
     pub fn aead_cipher() -> Box<dyn AeadCipher> {
         Box::new(Self)
     }
+    
+    /// Creates a nonce from a 16 byte `salt` followed by the 8 byte message `counter` in the given
+    /// byte order (`salt || counter`)
+    ///
+    /// Returns `ApiMisuse(SaltLen)` if `salt` is not exactly 16 bytes long. _Note: The counter must
+    /// never repeat for the same key and salt._
+    pub fn nonce_from_counter(salt: &[u8], counter: u64, endian: Endian)
+        -> Result<[u8; XCHACHAPOLY_NONCE], ChachaPolyError>
+    {
+        let mut nonce = [0; XCHACHAPOLY_NONCE];
+        nonce_from_counter(&mut nonce, salt, counter, endian)?;
+        Ok(nonce)
+    }
+    
+    /// Encrypts `plaintext` into `buf` and authenticates it with `ad` into a detached tag
+    ///
+    /// This is the counterpart to `open_detached_to` for formats where the ciphertext and the tag
+    /// are stored in separate buffers; the ciphertext length is always `plaintext.len()`.
+    pub fn seal_detached_to(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8],
+        nonce: &[u8]) -> Result<Tag, ChachaPolyError>
+    {
+        // Verify input
+        vfy_enc!(
+            key => [XCHACHAPOLY_KEY], nonce => [XCHACHAPOLY_NONCE],
+            plaintext => [buf, XCHACHAPOLY_MAX]
+        );
+        
+        // Copy the plaintext into buf and seal in place
+        let (data, mut tag) = (&mut buf[..plaintext.len()], Tag([0; XCHACHAPOLY_TAG]));
+        data.copy_from_slice(plaintext);
+        xchachapoly_seal(data, &mut tag.0, ad, key, nonce);
+        Ok(tag)
+    }
+    /// Validates `ciphertext` with `ad` against the detached `tag` and decrypts it into `buf`
+    ///
+    /// This is the counterpart to `open_to` for formats where the ciphertext and the tag are stored
+    /// in separate buffers. Returns the plaintext length (which is always `ciphertext.len()`).
+    pub fn open_detached_to(&self, buf: &mut[u8], ciphertext: &[u8], tag: &[u8; XCHACHAPOLY_TAG],
+        ad: &[u8], key: &[u8], nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_dec!(
+            key => [XCHACHAPOLY_KEY], nonce => [XCHACHAPOLY_NONCE],
+            ciphertext => [buf, XCHACHAPOLY_MAX]
+        );
+        
+        // Copy the ciphertext into buf and decrypt in place
+        xchachapoly_open_to(buf, ciphertext, tag, ad, key, nonce)?;
+        Ok(ciphertext.len())
+    }
+    
+    /// Encrypts `plaintext` and authenticates it with `ad` into `buf` using the tag-prefixed
+    /// layout `tag || ciphertext`
+    ///
+    /// This is the counterpart to `seal_to` for formats that place the tag in front of the
+    /// ciphertext; the tag itself is computed exactly as for `seal_to`. Returns the sealed length.
+    pub fn seal_tag_prefixed(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8],
+        nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_seal!(
+            key => [XCHACHAPOLY_KEY], nonce => [XCHACHAPOLY_NONCE],
+            plaintext => [buf, XCHACHAPOLY_MAX]
+        );
+        
+        // Copy the plaintext behind the tag and seal in place
+        let (tag, data) = buf.split_at_mut(XCHACHAPOLY_TAG);
+        let data = &mut data[..plaintext.len()];
+        data.copy_from_slice(plaintext);
+        xchachapoly_seal(data, tag, ad, key, nonce);
+        Ok(XCHACHAPOLY_TAG + plaintext.len())
+    }
+    /// Validates the tag-prefixed `ciphertext` (`tag || ciphertext`) with `ad` and decrypts it into
+    /// `buf`
+    ///
+    /// This is the counterpart to `open_to` for formats that place the tag in front of the
+    /// ciphertext. Returns the plaintext length.
+    pub fn open_tag_prefixed(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], key: &[u8],
+        nonce: &[u8]) -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        vfy_open!(
+            key => [XCHACHAPOLY_KEY], nonce => [XCHACHAPOLY_NONCE],
+            ciphertext => [buf, XCHACHAPOLY_TAG, XCHACHAPOLY_MAX]
+        );
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let (tag, data) = ciphertext.split_at(XCHACHAPOLY_TAG);
+        xchachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok(data.len())
+    }
+    
+    /// Encrypts `plaintext` into `buf` and authenticates it with `ad` like `seal_to`, but takes the
+    /// key and the nonce as arrays so that their lengths are checked at compile time
+    ///
+    /// Returns the sealed length.
+    pub fn seal_to_arr(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8],
+        key: &[u8; XCHACHAPOLY_KEY], nonce: &[u8; XCHACHAPOLY_NONCE])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        if plaintext.len() > XCHACHAPOLY_MAX {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        
+        // Copy the plaintext into buf and seal in place
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), XCHACHAPOLY_TAG)?;
+        data.copy_from_slice(plaintext);
+        xchachapoly_seal(data, tag, ad, key, nonce);
+        Ok(plaintext.len() + XCHACHAPOLY_TAG)
+    }
+    /// Validates `ciphertext` with `ad` and decrypts it into `buf` like `open_to`, but takes the
+    /// key and the nonce as arrays so that their lengths are checked at compile time
+    ///
+    /// Returns the plaintext length.
+    pub fn open_to_arr(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8],
+        key: &[u8; XCHACHAPOLY_KEY], nonce: &[u8; XCHACHAPOLY_NONCE])
+        -> Result<usize, ChachaPolyError>
+    {
+        // Verify input
+        if ciphertext.len() > XCHACHAPOLY_MAX {
+            Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))?
+        }
+        let data_len = ciphertext.len().checked_sub(XCHACHAPOLY_TAG)
+            .ok_or(ChachaPolyError::TruncatedInput)?;
+        if buf.len() < data_len {
+            let misuse = Misuse::BufferTooSmall{ needed: data_len, got: buf.len() };
+            Err(ChachaPolyError::ApiMisuse(misuse))?
+        }
+        
+        // Copy the ciphertext into buf and decrypt in place
+        let (data, tag) = ciphertext.split_at(data_len);
+        xchachapoly_open_to(buf, data, tag, ad, key, nonce)?;
+        Ok(data_len)
+    }
 }
 impl SecKeyGen for XChachaPoly {
     fn new_sec_key(&self, buf: &mut[u8], rng: &mut dyn SecureRng) -> Result<usize, Box<dyn Error + 'static>> {
@@ -127,8 +309,8 @@ impl AeadCipher for XChachaPoly {
         );
         
         // Seal the data
-        let (data, tag) = buf.split_at_mut(plaintext_len);
-        xchachapoly_seal(data, &mut tag[..XCHACHAPOLY_TAG], ad, key, nonce);
+        let (data, tag) = split_tag_mut(buf, plaintext_len, XCHACHAPOLY_TAG)?;
+        xchachapoly_seal(data, tag, ad, key, nonce);
         Ok(plaintext_len + XCHACHAPOLY_TAG)
     }
     fn seal_to(&self, buf: &mut[u8], plaintext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
@@ -141,9 +323,9 @@ impl AeadCipher for XChachaPoly {
         );
         
         // Copy the plaintext into buf and seal in place
-        let (data, tag) = buf.split_at_mut(plaintext.len());
+        let (data, tag) = split_tag_mut(buf, plaintext.len(), XCHACHAPOLY_TAG)?;
         data.copy_from_slice(plaintext);
-        xchachapoly_seal(data, &mut tag[..XCHACHAPOLY_TAG], ad, key, nonce);
+        xchachapoly_seal(data, tag, ad, key, nonce);
         Ok(plaintext.len() + XCHACHAPOLY_TAG)
     }
     
@@ -151,15 +333,16 @@ impl AeadCipher for XChachaPoly {
         -> Result<usize, Box<dyn Error + 'static>>
     {
         // Verify input
-        vfy_open!(
+        vfy_open_in_place!(
             key => [XCHACHAPOLY_KEY], nonce => [XCHACHAPOLY_NONCE],
             ciphertext_len => [buf, XCHACHAPOLY_TAG, XCHACHAPOLY_MAX]
         );
         
         // Open the data
-        let (data, tag) = buf.split_at_mut(ciphertext_len - XCHACHAPOLY_TAG);
-        xchachapoly_open(data, &tag[..XCHACHAPOLY_TAG], ad, key, nonce)?;
-        Ok(ciphertext_len - XCHACHAPOLY_TAG)
+        let plaintext_len = ciphertext_len - XCHACHAPOLY_TAG;
+        let (data, tag) = split_tag_mut(buf, plaintext_len, XCHACHAPOLY_TAG)?;
+        xchachapoly_open(data, tag, ad, key, nonce)?;
+        Ok(plaintext_len)
     }
     fn open_to(&self, buf: &mut[u8], ciphertext: &[u8], ad: &[u8], key: &[u8], nonce: &[u8])
         -> Result<usize, Box<dyn Error + 'static>>
@@ -172,8 +355,7 @@ impl AeadCipher for XChachaPoly {
         
         // Copy the ciphertext into buf and decrypt in place
         let (data, tag) = ciphertext.split_at(ciphertext.len() - XCHACHAPOLY_TAG);
-        buf[..data.len()].copy_from_slice(data);
-        xchachapoly_open(&mut buf[..data.len()], &tag[..XCHACHAPOLY_TAG], ad, key, nonce)?;
+        xchachapoly_open_to(buf, data, &tag[..XCHACHAPOLY_TAG], ad, key, nonce)?;
         Ok(ciphertext.len() - XCHACHAPOLY_TAG)
     }
 }
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crypto_api_chachapoly::{ AdBuilder, ChachaPolyIetf, crypto_api::cipher::AeadCipher };
+
+
+#[test]
+fn test_encoding() {
+    let mut ad = AdBuilder::new();
+    ad.push(b"").push(b"\x01\x02");
+    assert_eq!(ad.as_bytes(), b"\0\0\0\0\0\0\0\0\x02\0\0\0\0\0\0\0\x01\x02");
+    assert_eq!(ad.clone().finish(), ad.as_bytes());
+    assert!(AdBuilder::new().finish().is_empty());
+}
+
+
+#[test]
+fn test_field_boundaries() {
+    let (key, nonce) = ([0x2a; 32], [0x17; 12]);
+    
+    // A different split of the same bytes must result in a different AD
+    let mut ad_a = AdBuilder::new();
+    ad_a.push(b"00").push(b"11");
+    let mut ad_b = AdBuilder::new();
+    ad_b.push(b"0").push(b"011");
+    assert_ne!(ad_a.as_bytes(), ad_b.as_bytes());
+    
+    // ... and thus in a different tag
+    let (mut sealed_a, mut sealed_b) = (vec![0; 16], vec![0; 16]);
+    ChachaPolyIetf.seal_to(&mut sealed_a, b"", ad_a.as_bytes(), &key, &nonce).unwrap();
+    ChachaPolyIetf.seal_to(&mut sealed_b, b"", ad_b.as_bytes(), &key, &nonce).unwrap();
+    assert_ne!(sealed_a, sealed_b);
+    
+    // Opening with the other split must fail
+    let mut buf = vec![0; 0];
+    assert!(ChachaPolyIetf.open_to(&mut buf, &sealed_a, ad_b.as_bytes(), &key, &nonce).is_err());
+    ChachaPolyIetf.open_to(&mut buf, &sealed_a, ad_a.as_bytes(), &key, &nonce).unwrap();
+}
\ No newline at end of file
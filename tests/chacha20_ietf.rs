@@ -1,7 +1,7 @@
 mod shared;
 
 use shared::{ JsonValueExt, ResultExt };
-use crypto_api_chachapoly::ChaCha20Ietf;
+use crypto_api_chachapoly::{ ChaCha20Ietf, ChachaPolyError, Misuse };
 use json::JsonValue;
 
 
@@ -9,6 +9,12 @@ use json::JsonValue;
 const TEST_VECTORS: &str = include_str!("chacha20_ietf.json");
 
 
+/// XORs `a` and `b`
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+
 /// A crypto test vector
 #[derive(Debug)]
 struct CryptoTestVector {
@@ -72,11 +78,29 @@ impl CryptoTestVector {
         
         self
     }
+    
+    /// Tests the raw keystream
+    pub fn test_keystream(&self) -> &Self {
+        // Compute the keystream and XOR it with the plaintext
+        let mut keystream = vec![0; self.plaintext.len()];
+        ChaCha20Ietf::keystream(&self.key, &self.nonce, 0, &mut keystream).unwrap();
+        let buf = xor(&self.plaintext, &keystream);
+        assert_eq!(buf, self.ciphertext, "Test vector: \"{}\"", self.name);
+        
+        // Compare a keystream with a start counter against `xor`
+        let mut keystream = vec![0; self.plaintext.len()];
+        ChaCha20Ietf::keystream(&self.key, &self.nonce, 7, &mut keystream).unwrap();
+        let mut buf = self.plaintext.clone();
+        ChaCha20Ietf::xor(&self.key, &self.nonce, 7, &mut buf);
+        assert_eq!(buf, xor(&self.plaintext, &keystream), "Test vector: \"{}\"", self.name);
+        
+        self
+    }
 }
 #[test]
 fn test_crypto() {
     for vec in CryptoTestVector::load() {
-        vec.test_encryption().test_decryption();
+        vec.test_encryption().test_decryption().test_keystream();
     }
 }
 
@@ -170,4 +194,226 @@ fn test_api() {
     for vec in ApiTestVector::load() {
         vec.test_encryption().test_decryption();
     }
+}
+
+#[test]
+fn test_keystream_api() {
+    let (key, nonce) = (vec![0; 32], vec![0; 12]);
+    let mut out = vec![0; 129];
+    
+    // Invalid key and nonce lengths
+    assert_eq!(
+        ChaCha20Ietf::keystream(&key[..31], &nonce, 0, &mut out),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    assert_eq!(
+        ChaCha20Ietf::keystream(&key, &nonce[..11], 0, &mut out),
+        Err(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 12, got: 11 }))
+    );
+    
+    // The last block can be used but the counter must not overflow
+    ChaCha20Ietf::keystream(&key, &nonce, u32::MAX, &mut out[..64]).unwrap();
+    assert_eq!(
+        ChaCha20Ietf::keystream(&key, &nonce, u32::MAX, &mut out[..65]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))
+    );
+    assert_eq!(
+        ChaCha20Ietf::keystream(&key, &nonce, u32::MAX - 1, &mut out),
+        Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))
+    );
+}
+#[test]
+fn test_xor_last_block() {
+    let (key, nonce) = (vec![0x17; 32], vec![0x2a; 12]);
+    let mut keystream = vec![0; 64];
+    ChaCha20Ietf::keystream(&key, &nonce, u32::MAX, &mut keystream).unwrap();
+    
+    // Like `keystream`, `xor` and `xor_chunks` can use the last block
+    let mut buf = vec![0; 64];
+    ChaCha20Ietf::xor(&key, &nonce, u32::MAX, &mut buf);
+    assert_eq!(buf, keystream);
+    
+    let mut buf = vec![0; 64];
+    let (a, b) = buf.split_at_mut(10);
+    ChaCha20Ietf::xor_chunks(&key, &nonce, u32::MAX, &mut [a, b]);
+    assert_eq!(buf, keystream);
+}
+#[test]
+#[should_panic(expected = "The ChaCha20-IETF block counter must not exceed 2^32 - 1")]
+fn test_xor_beyond_last_block() {
+    let (key, nonce) = (vec![0x17; 32], vec![0x2a; 12]);
+    ChaCha20Ietf::xor(&key, &nonce, u32::MAX, &mut [0; 65]);
+}
+#[test]
+#[should_panic(expected = "The ChaCha20-IETF block counter must not exceed 2^32 - 1")]
+fn test_xor_chunks_beyond_last_block() {
+    let (key, nonce) = (vec![0x17; 32], vec![0x2a; 12]);
+    let (mut a, mut b) = ([0; 64], [0; 1]);
+    ChaCha20Ietf::xor_chunks(&key, &nonce, u32::MAX, &mut [&mut a, &mut b]);
+}
+
+
+#[test]
+fn test_xor_ext() {
+    // Generated with libsodium's `crypto_stream_chacha20_xor_ic` with `ic = 2^32 - 1`
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0, 1, 2, 3, 4, 5, 6, 7];
+    let expected = hex::decode(concat!(
+        "a2b8d04b13877b4a7013cb9031e4b70836e9705a9691bd18f8fca48502eacdcae0b8faaeef6c5dfee436afd82",
+        "68aa6385dabb2855761127a3946b50d649f9a4b2fcab2c09a960545c6f57e9269ebc22b4ed12782e66dc4cb61",
+        "2536f5cdbed4bcba16af8a92140bf4ded4808af8eee82bd0f18fbb64f073c2a547bc2372528f36"
+    )).unwrap();
+    
+    // The counter must cross the 32 bit boundary without wrapping
+    let mut buf = vec![0; 128];
+    ChaCha20Ietf::xor_ext(&key, &nonce, u32::MAX as u64, &mut buf);
+    assert_eq!(buf, expected);
+    
+    // The second block must be the block `2^32`
+    let mut buf = vec![0; 64];
+    ChaCha20Ietf::xor_ext(&key, &nonce, 1 << 32, &mut buf);
+    assert_eq!(buf, &expected[64..]);
+}
+#[test]
+fn test_xor_chunks() {
+    let (key, nonce): (Vec<u8>, Vec<u8>) = ((0..32).collect(), (0..12).collect());
+    let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    
+    // Compute the expected result over the concatenation
+    let mut expected = data.clone();
+    ChaCha20Ietf::xor(&key, &nonce, 1, &mut expected);
+    
+    // Split the data at various positions (mostly mid-block and including empty chunks)
+    let layouts: &[&[usize]] = &[
+        &[300], &[1, 299], &[63, 237], &[64, 236], &[65, 235],
+        &[0, 17, 0, 100, 3, 60, 120], &[1; 300], &[31, 33, 31, 33, 172]
+    ];
+    for layout in layouts {
+        // Split the data into the chunks
+        let mut buf = data.clone();
+        let mut chunks = Vec::new();
+        let mut rest = &mut buf[..];
+        for &len in layout.iter() {
+            let (chunk, tail) = rest.split_at_mut(len);
+            chunks.push(chunk);
+            rest = tail;
+        }
+        
+        // XOR the chunks and compare the concatenation
+        ChaCha20Ietf::xor_chunks(&key, &nonce, 1, &mut chunks);
+        assert_eq!(buf, expected, "Layout: {:?}", layout);
+    }
+}
+#[test]
+fn test_xor_batches() {
+    let (key, nonce): (Vec<u8>, Vec<u8>) = ((0..32).collect(), (0..12).collect());
+    for &len in [0, 1, 63, 64, 65, 255, 256, 257, 319, 320, 321, 1000, 1025].iter() {
+        // Compute the expected result blockwise
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let mut expected = data.clone();
+        for (i, block) in expected.chunks_mut(64).enumerate() {
+            let mut keystream = vec![0; 64];
+            ChaCha20Ietf::keystream(&key, &nonce, 7 + i as u32, &mut keystream).unwrap();
+            block.iter_mut().zip(keystream.iter()).for_each(|(b, k)| *b ^= k);
+        }
+        
+        // The batched XOR must be byte-identical
+        let mut buf = data.clone();
+        ChaCha20Ietf::xor(&key, &nonce, 7, &mut buf);
+        assert_eq!(buf, expected, "Length: {}", len);
+    }
+    
+    // A batch must not require blocks beyond the last block
+    let mut buf = vec![0; 64];
+    ChaCha20Ietf::xor(&key, &nonce, u32::MAX - 1, &mut buf);
+    let mut expected = vec![0; 64];
+    ChaCha20Ietf::keystream(&key, &nonce, u32::MAX - 1, &mut expected).unwrap();
+    assert_eq!(buf, expected);
+}
+#[test]
+fn test_blocks_for_len() {
+    assert_eq!(ChaCha20Ietf::blocks_for_len(0), 0);
+    assert_eq!(ChaCha20Ietf::blocks_for_len(1), 1);
+    assert_eq!(ChaCha20Ietf::blocks_for_len(63), 1);
+    assert_eq!(ChaCha20Ietf::blocks_for_len(64), 1);
+    assert_eq!(ChaCha20Ietf::blocks_for_len(65), 2);
+    assert_eq!(ChaCha20Ietf::blocks_for_len(128), 2);
+    assert_eq!(ChaCha20Ietf::blocks_for_len(usize::MAX), (usize::MAX as u64).div_ceil(64));
+}
+#[test]
+fn test_blocks_for_len_boundary() {
+    // `n + blocks_for_len(len) == 2^32` is the last accepted length
+    let (key, nonce, n) = (vec![0x17; 32], vec![0x2a; 12], u32::MAX - 1);
+    assert_eq!(n as u64 + ChaCha20Ietf::blocks_for_len(128), 4_294_967_296);
+    assert_eq!(n as u64 + ChaCha20Ietf::blocks_for_len(129), 4_294_967_297);
+    
+    let mut keystream = vec![0; 128];
+    ChaCha20Ietf::keystream(&key, &nonce, n, &mut keystream).unwrap();
+    let mut buf = vec![0; 128];
+    ChaCha20Ietf::xor(&key, &nonce, n, &mut buf);
+    assert_eq!(buf, keystream);
+    
+    // One more byte needs a block beyond the last block
+    let result = std::panic::catch_unwind(|| ChaCha20Ietf::xor(&key, &nonce, n, &mut [0; 129]));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_in_place() {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+    for &len in [0, 1, 63, 64, 65, 300].iter() {
+        // Encrypt the full buffer via the `Cipher` interface
+        let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let mut expected = plaintext.clone();
+        ChaCha20Ietf::cipher().encrypt(&mut expected, len, &key, &nonce).unwrap();
+        
+        // Encrypt and decrypt in place
+        let mut buf = plaintext.clone();
+        ChaCha20Ietf::encrypt_in_place(&mut buf, &key, &nonce).unwrap();
+        assert_eq!(buf, expected, "Length: {}", len);
+        ChaCha20Ietf::decrypt_in_place(&mut buf, &key, &nonce).unwrap();
+        assert_eq!(buf, plaintext, "Length: {}", len);
+    }
+    
+    // Invalid parameters
+    let mut buf = vec![0; 16];
+    assert_eq!(
+        ChaCha20Ietf::encrypt_in_place(&mut buf, &key[..31], &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    assert_eq!(
+        ChaCha20Ietf::decrypt_in_place(&mut buf, &key, &nonce[1..]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 12, got: 11 }))
+    );
+}
+
+#[test]
+fn test_rfc8439_2_4_2() {
+    // The example from RFC 8439 section 2.4.2 (the keystream starts with block 1)
+    let key: Vec<u8> = (0..32).collect();
+    let nonce = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00];
+    let plaintext = concat!(
+        "Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the ",
+        "future, sunscreen would be it."
+    ).as_bytes();
+    let keystream = hex::decode(concat!(
+        "224f51f3401bd9e12fde276fb8631ded8c131f823d2c06e27e4fcaec9ef3cf788a3b0aa372600a92b57974cd",
+        "ed2b9334794cba40c63e34cdea212c4cf07d41b769a6749f3f630f4122cafe28ec4dc47e26d4346d70b98c73",
+        "f3e9c53ac40c5945398b6eda1a832c89c167eacd901d7e2bf363"
+    )).unwrap();
+    let ciphertext = hex::decode(concat!(
+        "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0bf91b65c5524733ab8f593dab",
+        "cd62b3571639d624e65152ab8f530c359f0861d807ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806",
+        "818ce91ab77937365af90bbf74a35be6b40b8eedf2785e42874d"
+    )).unwrap();
+    
+    // Validate the keystream
+    let mut buf = vec![0; plaintext.len()];
+    ChaCha20Ietf::keystream(&key, &nonce, 1, &mut buf).unwrap();
+    assert_eq!(buf, keystream);
+    
+    // Validate the encryption
+    let mut buf = plaintext.to_vec();
+    ChaCha20Ietf::xor(&key, &nonce, 1, &mut buf);
+    assert_eq!(buf, ciphertext);
 }
\ No newline at end of file
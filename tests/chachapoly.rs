@@ -0,0 +1,322 @@
+mod shared;
+
+use shared::{ JsonValueExt, ResultExt };
+use crypto_api_chachapoly::{ ChachaPoly, ChachaPolyError, Misuse, crypto_api::cipher::AeadCipher };
+use json::JsonValue;
+
+
+/// The test vectors
+const TEST_VECTORS: &str = include_str!("chachapoly.json");
+
+
+/// A crypto test vector
+#[derive(Debug)]
+struct CryptoTestVector {
+    name: String,
+    key: Vec<u8>,
+    nonce: Vec<u8>,
+    ad: Vec<u8>,
+    plaintext: Vec<u8>,
+    ciphertext: Vec<u8>
+}
+impl CryptoTestVector {
+    /// Loads the test vectors
+    pub fn load() -> Vec<Self> {
+        let json = json::parse(TEST_VECTORS).unwrap();
+        let mut vecs = Vec::new();
+        for vec in json["crypto"].checked_array_iter() {
+            vecs.push(Self {
+                name: vec["name"].checked_string(),
+                key: vec["key"].checked_bytes(),
+                nonce: vec["nonce"].checked_bytes(),
+                ad: vec["ad"].checked_bytes(),
+                plaintext: vec["plaintext"].checked_bytes(),
+                ciphertext: vec["ciphertext"].checked_bytes(),
+            });
+        }
+        vecs
+    }
+    
+    /// Tests the encryption
+    pub fn test_encryption(&self) -> &Self {
+        // Encrypt in place
+        let mut buf = self.plaintext.clone();
+        buf.extend_from_slice(&[0; 16]);
+        ChachaPoly::aead_cipher()
+            .seal(&mut buf, self.plaintext.len(), &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(buf, self.ciphertext, "Test vector: \"{}\"", self.name);
+        
+        // Encrypt to buffer
+        let mut buf = vec![0; self.ciphertext.len()];
+        ChachaPoly::aead_cipher()
+            .seal_to(&mut buf, &self.plaintext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(buf, self.ciphertext, "Test vector: \"{}\"", self.name);
+        
+        self
+    }
+    
+    /// Tests the decryption
+    pub fn test_decryption(&self) -> &Self {
+        // Decrypt in place
+        let mut buf = self.ciphertext.clone();
+        let len = ChachaPoly::aead_cipher()
+            .open(&mut buf, self.ciphertext.len(), &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(&buf[..len], self.plaintext.as_slice(), "Test vector: \"{}\"", self.name);
+        
+        // Decrypt to buffer
+        let mut buf = vec![0; self.plaintext.len()];
+        ChachaPoly::aead_cipher()
+            .open_to(&mut buf, &self.ciphertext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
+        
+        self
+    }
+}
+#[test]
+fn test_crypto() {
+    for vec in CryptoTestVector::load() {
+        vec.test_encryption().test_decryption();
+    }
+}
+
+
+/// A MAC-error test vector
+#[derive(Debug)]
+struct ErrorTestVector {
+    name: String,
+    key: Vec<u8>,
+    nonce: Vec<u8>,
+    ad: Vec<u8>,
+    ciphertext: Vec<u8>
+}
+impl ErrorTestVector {
+    /// Loads the test vectors
+    pub fn load() -> Vec<Self> {
+        let json = json::parse(TEST_VECTORS).unwrap();
+        let mut vecs = Vec::new();
+        for vec in json["error"].checked_array_iter() {
+            vecs.push(Self {
+                name: vec["name"].checked_string(),
+                key: vec["key"].checked_bytes(),
+                nonce: vec["nonce"].checked_bytes(),
+                ad: vec["ad"].checked_bytes(),
+                ciphertext: vec["ciphertext"].checked_bytes(),
+            });
+        }
+        vecs
+    }
+    
+    /// Tests the decryption
+    pub fn test_decryption(&self) -> &Self {
+        // Decrypt in place
+        let mut buf = self.ciphertext.clone();
+        let error = ChachaPoly::aead_cipher()
+            .open(&mut buf, self.ciphertext.len(), &self.ad, &self.key, &self.nonce)
+            .error_or(format!("Test vector: \"{}\"", self.name));
+        assert_eq!(error.to_string(), "InvalidData", "Test vector: \"{}\"", self.name);
+        
+        // Decrypt to buffer
+        let mut buf = vec![0; self.ciphertext.len()];
+        let error = ChachaPoly::aead_cipher()
+            .open_to(&mut buf, &self.ciphertext, &self.ad, &self.key, &self.nonce)
+            .error_or(format!("Test vector: \"{}\"", self.name));
+        assert_eq!(error.to_string(), "InvalidData", "Test vector: \"{}\"", self.name);
+        
+        self
+    }
+}
+#[test]
+fn test_error() {
+    for vec in ErrorTestVector::load() {
+        vec.test_decryption();
+    }
+}
+
+
+/// An API test vector
+#[derive(Default, Clone, Debug)]
+pub struct ApiTestVector {
+    name: String,
+    key_len: usize,
+    nonce_len: usize,
+    ad_len: usize,
+    enc_input_len: usize,
+    enc_buf_len: usize,
+    dec_input_len: usize,
+    dec_buf_len: usize,
+    enc_error: String,
+    dec_error: String,
+    dec_in_place_error: String
+}
+impl ApiTestVector {
+    /// Loads the test vectors
+    pub fn load() -> Vec<Self> {
+        // Load the JSON and create the default struct
+        let json = json::parse(TEST_VECTORS).unwrap();
+        let mut defaults = Self::default();
+        defaults.load_json(&json["api"]["defaults"]);
+        
+        // Load the test vectors
+        let mut vecs = Vec::new();
+        for vec in json["api"]["tests"].members() {
+            let mut this = defaults.clone();
+            this.load_json(vec);
+            vecs.push(this);
+        }
+        vecs
+    }
+    
+    /// Tests the encryption
+    pub fn test_encryption(&self) -> &Self {
+        // Prepare fake inputs
+        let key = vec![0; self.key_len];
+        let nonce = vec![0; self.nonce_len];
+        let ad = vec![0; self.ad_len];
+        let input = vec![0; self.enc_input_len];
+        let mut buf = vec![0; self.enc_buf_len];
+        
+        // Encrypt in place
+        let error = ChachaPoly::aead_cipher()
+            .seal(&mut buf, input.len(), &ad, &key, &nonce)
+            .error_or(format!("Test vector: \"{}\"", self.name));
+        assert_eq!(error.to_string(), self.enc_error, "Test vector: \"{}\"", self.name);
+        
+        // Encrypt in buffer
+        let error = ChachaPoly::aead_cipher()
+            .seal_to(&mut buf, &input, &ad, &key, &nonce)
+            .error_or(format!("Test vector: \"{}\"", self.name));
+        assert_eq!(error.to_string(), self.enc_error, "Test vector: \"{}\"", self.name);
+        
+        self
+    }
+    
+    /// Tests the decryption
+    pub fn test_decryption(&self) -> &Self {
+        // Prepare fake inputs
+        let key = vec![0; self.key_len];
+        let nonce = vec![0; self.nonce_len];
+        let ad = vec![0; self.ad_len];
+        let input = vec![0; self.dec_input_len];
+        let mut buf = vec![0; self.dec_buf_len];
+        
+        // Decrypt in place
+        let error = ChachaPoly::aead_cipher()
+            .open(&mut buf, input.len(), &ad, &key, &nonce)
+            .error_or(format!("Test vector: \"{}\"", self.name));
+        let expected = &self.dec_in_place_error;
+        assert_eq!(&error.to_string(), expected, "Test vector: \"{}\"", self.name);
+        
+        // Decrypt in buffer
+        let error = ChachaPoly::aead_cipher()
+            .open_to(&mut buf, &input, &ad, &key, &nonce)
+            .error_or(format!("Test vector: \"{}\"", self.name));
+        assert_eq!(error.to_string(), self.dec_error, "Test vector: \"{}\"", self.name);
+        
+        self
+    }
+    
+    /// Loads all existing/non-null fields from `j` into `self`
+    fn load_json(&mut self, j: &JsonValue) {
+        self.name = j["name"].optional_string(&self.name);
+        self.key_len = j["key_len"].optional_usize(self.key_len);
+        self.nonce_len = j["nonce_len"].optional_usize(self.nonce_len);
+        self.ad_len = j["ad_len"].optional_usize(self.ad_len);
+        self.enc_input_len = j["enc_input_len"].optional_usize(self.enc_input_len);
+        self.enc_buf_len = j["enc_buf_len"].optional_usize(self.enc_buf_len);
+        self.dec_input_len = j["dec_input_len"].optional_usize(self.dec_input_len);
+        self.dec_buf_len = j["dec_buf_len"].optional_usize(self.dec_buf_len);
+        self.enc_error = j["error"].optional_string(&self.enc_error);
+        self.enc_error = j["enc_error"].optional_string(&self.enc_error);
+        self.dec_error = j["error"].optional_string(&self.dec_error);
+        self.dec_error = j["dec_error"].optional_string(&self.dec_error);
+        self.dec_in_place_error = j["dec_in_place_error"].optional_string(&self.dec_error);
+    }
+}
+#[test]
+fn test_api() {
+    for vec in ApiTestVector::load() {
+        vec.test_encryption().test_decryption();
+    }
+}
+
+#[test]
+fn test_api_huge_len() {
+    let (key, nonce, mut buf) = (vec![0; 32], vec![0; 8], vec![0; 64]);
+    
+    /// Asserts that `$result` failed with `ApiMisuse($misuse)`
+    macro_rules! assert_misuse {
+        ($result:expr, $misuse:expr) => ({
+            let error = $result.error_or("The operation must fail");
+            let error = error.downcast_ref::<ChachaPolyError>().unwrap();
+            assert_eq!(error, &ChachaPolyError::ApiMisuse($misuse));
+        });
+    }
+    
+    // Claimed lengths beyond the limit
+    assert_misuse!(
+        ChachaPoly::aead_cipher().seal(&mut buf, usize::MAX, &[], &key, &nonce),
+        Misuse::TooMuchData
+    );
+    assert_misuse!(
+        ChachaPoly::aead_cipher().open(&mut buf, usize::MAX, &[], &key, &nonce),
+        Misuse::TooMuchData
+    );
+    
+    // Claimed lengths within the limit but way beyond the buffer size
+    assert_misuse!(
+        ChachaPoly::aead_cipher().seal(&mut buf, 1 << 30, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: (1 << 30) + 16, got: 64 }
+    );
+    assert_misuse!(
+        ChachaPoly::aead_cipher().open(&mut buf, 1 << 30, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: 1 << 30, got: 64 }
+    );
+    
+    // A buffer that cannot even hold the tag
+    assert_misuse!(
+        ChachaPoly::aead_cipher().seal(&mut buf[..15], 0, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: 16, got: 15 }
+    );
+}
+
+#[test]
+fn test_open_buffer_one_byte_too_small() {
+    let (key, nonce) = (vec![0; 32], vec![0; 8]);
+    for &len in [16, 17, 50, 64, 65].iter() {
+        // Create a buffer that can hold the plaintext and all but one byte of the tag
+        let mut buf = vec![0; len - 1];
+        let expected = ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{
+            needed: len, got: len - 1
+        });
+        
+        // Opening in place must fail cleanly instead of panicking
+        let error = ChachaPoly.open(&mut buf, len, &[], &key, &nonce)
+            .error_or("The operation must fail");
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&expected));
+    }
+}
+
+
+#[test]
+fn test_oversized_buffer() {
+    let (key, nonce, plaintext) = (vec![0x07; 32], vec![0x0e; 8], vec![0x17; 100]);
+    let mut ciphertext = vec![0; plaintext.len() + 16];
+    ChachaPoly.seal_to(&mut ciphertext, &plaintext, &[], &key, &nonce).unwrap();
+    
+    // Open into an oversized buffer; only the plaintext must be written
+    let mut buf = vec![0xAA; plaintext.len() + 64];
+    let len = ChachaPoly.open_to(&mut buf, &ciphertext, &[], &key, &nonce).unwrap();
+    assert_eq!(&buf[..len], plaintext.as_slice());
+    assert!(buf[len..].iter().all(|b| *b == 0xAA));
+    
+    // A failed open must erase the written bytes and must not touch the tail
+    ciphertext[0] ^= 0x01;
+    let mut buf = vec![0xAA; plaintext.len() + 64];
+    ChachaPoly.open_to(&mut buf, &ciphertext, &[], &key, &nonce).unwrap_err();
+    assert!(buf[..plaintext.len()].iter().all(|b| *b == 0x00));
+    assert!(buf[plaintext.len()..].iter().all(|b| *b == 0xAA));
+}
\ No newline at end of file
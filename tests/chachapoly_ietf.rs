@@ -1,8 +1,12 @@
 mod shared;
 
 use shared::{ JsonValueExt, ResultExt };
-use crypto_api_chachapoly::ChachaPolyIetf;
+use crypto_api_chachapoly::{
+    ChachaPolyIetf, ChaCha20Ietf, Poly1305, ChachaPolyError, Misuse, Tag, chachapoly_seal, chachapoly_open, crypto_api::cipher::AeadCipher,
+    primitives
+};
 use json::JsonValue;
+use std::{ convert::TryInto, io::{ Cursor, Read } };
 
 
 /// The test vectors
@@ -54,6 +58,32 @@ impl CryptoTestVector {
             .unwrap();
         assert_eq!(buf, self.ciphertext, "Test vector: \"{}\"", self.name);
         
+        // Encrypt using the raw construction
+        let (mut data, mut tag) = (self.plaintext.clone(), vec![0; 16]);
+        chachapoly_seal(&mut data, &mut tag, &self.ad, &self.key, &self.nonce);
+        data.extend_from_slice(&tag);
+        assert_eq!(data, self.ciphertext, "Test vector: \"{}\"", self.name);
+        
+        // Encrypt in a vector
+        let mut buf = self.plaintext.clone();
+        ChachaPolyIetf.seal_vec(&mut buf, &self.ad, &self.key, &self.nonce).unwrap();
+        assert_eq!(buf, self.ciphertext, "Test vector: \"{}\"", self.name);
+        
+        // Encrypt to buffer with a detached tag
+        let (ciphertext, tag) = self.split_tag();
+        let mut buf = vec![0; self.plaintext.len()];
+        let detached = ChachaPolyIetf
+            .seal_detached_to(&mut buf, &self.plaintext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(buf, ciphertext, "Test vector: \"{}\"", self.name);
+        assert_eq!(detached, Tag(tag), "Test vector: \"{}\"", self.name);
+        
+        // Recompute the tag over the ciphertext
+        let computed = ChachaPolyIetf
+            .compute_tag(&ciphertext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(computed, Tag(tag), "Test vector: \"{}\"", self.name);
+        
         self
     }
     
@@ -73,13 +103,74 @@ impl CryptoTestVector {
             .unwrap();
         assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
         
+        // Decrypt using the raw construction
+        let (mut data, tag) = self.split_tag();
+        chachapoly_open(&mut data, &tag, &self.ad, &self.key, &self.nonce).unwrap();
+        assert_eq!(data, self.plaintext, "Test vector: \"{}\"", self.name);
+        
+        // Decrypt in a vector
+        let mut buf = self.ciphertext.clone();
+        ChachaPolyIetf.open_vec(&mut buf, &self.ad, &self.key, &self.nonce).unwrap();
+        assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
+        
+        // Decrypt with ciphertext and tag in separate buffers
+        let (ciphertext, tag) = self.split_tag();
+        let mut buf = vec![0; self.plaintext.len()];
+        let len = ChachaPolyIetf
+            .open_detached_to(&mut buf, &ciphertext, &tag, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(len, self.plaintext.len(), "Test vector: \"{}\"", self.name);
+        assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
+        
         self
     }
+    
+    /// Tests the tag-prefixed layout
+    pub fn test_tag_prefixed(&self) -> &Self {
+        // The prefixed layout must be the suffixed layout with the tag moved to the front
+        let (ciphertext, tag) = self.split_tag();
+        let prefixed = [&tag[..], &ciphertext].concat();
+        
+        // Seal with a prefixed tag
+        let mut buf = vec![0; self.ciphertext.len()];
+        let len = ChachaPolyIetf
+            .seal_tag_prefixed(&mut buf, &self.plaintext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(len, self.ciphertext.len(), "Test vector: \"{}\"", self.name);
+        assert_eq!(buf, prefixed, "Test vector: \"{}\"", self.name);
+        
+        // Open with a prefixed tag
+        let mut buf = vec![0; self.plaintext.len()];
+        let len = ChachaPolyIetf
+            .open_tag_prefixed(&mut buf, &prefixed, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(len, self.plaintext.len(), "Test vector: \"{}\"", self.name);
+        assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
+        
+        // A suffixed ciphertext must not be accepted as prefixed one (unless it's only a tag)
+        if !self.plaintext.is_empty() {
+            let mut buf = vec![0; self.plaintext.len()];
+            let error = ChachaPolyIetf
+                .open_tag_prefixed(&mut buf, &self.ciphertext, &self.ad, &self.key, &self.nonce)
+                .unwrap_err();
+            assert_eq!(error, ChachaPolyError::InvalidData, "Test vector: \"{}\"", self.name);
+        }
+        
+        self
+    }
+    
+    /// Copies the ciphertext and the tag into two distinct allocations
+    fn split_tag(&self) -> (Vec<u8>, [u8; 16]) {
+        let (ciphertext, tag_slice) = self.ciphertext.split_at(self.ciphertext.len() - 16);
+        let mut tag = [0; 16];
+        tag.copy_from_slice(tag_slice);
+        (ciphertext.to_vec(), tag)
+    }
 }
 #[test]
 fn test_crypto() {
     for vec in CryptoTestVector::load() {
-        vec.test_encryption().test_decryption();
+        vec.test_encryption().test_decryption().test_tag_prefixed();
     }
 }
 
@@ -112,19 +203,38 @@ impl ErrorTestVector {
     
     /// Tests the decryption
     pub fn test_decryption(&self) -> &Self {
+        // A ciphertext that cannot even hold the tag is truncated instead of invalid
+        let expected = match self.ciphertext.len() {
+            len if len < 16 => "TruncatedInput",
+            _ => "InvalidData"
+        };
+        
         // Decrypt in place
         let mut buf = self.ciphertext.clone();
         let error = ChachaPolyIetf::aead_cipher()
             .open(&mut buf, self.ciphertext.len(), &self.ad, &self.key, &self.nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), "InvalidData", "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), expected, "Test vector: \"{}\"", self.name);
         
         // Decrypt to buffer
         let mut buf = vec![0; self.ciphertext.len()];
         let error = ChachaPolyIetf::aead_cipher()
             .open_to(&mut buf, &self.ciphertext, &self.ad, &self.key, &self.nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), "InvalidData", "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), expected, "Test vector: \"{}\"", self.name);
+        
+        // Decrypt with ciphertext and tag in separate buffers (if there is a complete tag)
+        if self.ciphertext.len() >= 16 {
+            let (ciphertext, tag_slice) = self.ciphertext.split_at(self.ciphertext.len() - 16);
+            let (ciphertext, mut tag) = (ciphertext.to_vec(), [0; 16]);
+            tag.copy_from_slice(tag_slice);
+            
+            let mut buf = vec![0; ciphertext.len()];
+            let error = ChachaPolyIetf
+                .open_detached_to(&mut buf, &ciphertext, &tag, &self.ad, &self.key, &self.nonce)
+                .error_or(format!("Test vector: \"{}\"", self.name));
+            assert_eq!(error.to_string(), expected, "Test vector: \"{}\"", self.name);
+        }
         
         self
     }
@@ -148,7 +258,9 @@ pub struct ApiTestVector {
     enc_buf_len: usize,
     dec_input_len: usize,
     dec_buf_len: usize,
-    error: String
+    enc_error: String,
+    dec_error: String,
+    dec_in_place_error: String
 }
 impl ApiTestVector {
     /// Loads the test vectors
@@ -181,13 +293,13 @@ impl ApiTestVector {
         let error = ChachaPolyIetf::aead_cipher()
             .seal(&mut buf, input.len(), &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), self.enc_error, "Test vector: \"{}\"", self.name);
         
         // Encrypt in buffer
         let error = ChachaPolyIetf::aead_cipher()
             .seal_to(&mut buf, &input, &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), self.enc_error, "Test vector: \"{}\"", self.name);
         
         self
     }
@@ -205,13 +317,14 @@ impl ApiTestVector {
         let error = ChachaPolyIetf::aead_cipher()
             .open(&mut buf, input.len(), &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        let expected = &self.dec_in_place_error;
+        assert_eq!(&error.to_string(), expected, "Test vector: \"{}\"", self.name);
         
         // Decrypt in buffer
         let error = ChachaPolyIetf::aead_cipher()
             .open_to(&mut buf, &input, &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), self.dec_error, "Test vector: \"{}\"", self.name);
         
         self
     }
@@ -226,7 +339,11 @@ impl ApiTestVector {
         self.enc_buf_len = j["enc_buf_len"].optional_usize(self.enc_buf_len);
         self.dec_input_len = j["dec_input_len"].optional_usize(self.dec_input_len);
         self.dec_buf_len = j["dec_buf_len"].optional_usize(self.dec_buf_len);
-        self.error = j["error"].optional_string(&self.error);
+        self.enc_error = j["error"].optional_string(&self.enc_error);
+        self.enc_error = j["enc_error"].optional_string(&self.enc_error);
+        self.dec_error = j["error"].optional_string(&self.dec_error);
+        self.dec_error = j["dec_error"].optional_string(&self.dec_error);
+        self.dec_in_place_error = j["dec_in_place_error"].optional_string(&self.dec_error);
     }
 }
 #[test]
@@ -234,4 +351,802 @@ fn test_api() {
     for vec in ApiTestVector::load() {
         vec.test_encryption().test_decryption();
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_api_huge_len() {
+    let (key, nonce, mut buf) = (vec![0; 32], vec![0; 12], vec![0; 64]);
+    
+    /// Asserts that `$result` failed with `ApiMisuse($misuse)`
+    macro_rules! assert_misuse {
+        ($result:expr, $misuse:expr) => ({
+            let error = $result.error_or("The operation must fail");
+            let error = error.downcast_ref::<ChachaPolyError>().unwrap();
+            assert_eq!(error, &ChachaPolyError::ApiMisuse($misuse));
+        });
+    }
+    
+    // Claimed lengths beyond the limit
+    assert_misuse!(
+        ChachaPolyIetf::aead_cipher().seal(&mut buf, usize::MAX, &[], &key, &nonce),
+        Misuse::TooMuchData
+    );
+    assert_misuse!(
+        ChachaPolyIetf::aead_cipher().open(&mut buf, usize::MAX, &[], &key, &nonce),
+        Misuse::TooMuchData
+    );
+    
+    // Claimed lengths within the limit but way beyond the buffer size
+    assert_misuse!(
+        ChachaPolyIetf::aead_cipher().seal(&mut buf, 1 << 30, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: (1 << 30) + 16, got: 64 }
+    );
+    assert_misuse!(
+        ChachaPolyIetf::aead_cipher().open(&mut buf, 1 << 30, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: 1 << 30, got: 64 }
+    );
+    
+    // Buffers that can hold the plaintext but not the entire ciphertext
+    for missing in 1..=16 {
+        assert_misuse!(
+            ChachaPolyIetf::aead_cipher().open(&mut buf[..64 - missing], 64, &[], &key, &nonce),
+            Misuse::BufferTooSmall{ needed: 64, got: 64 - missing }
+        );
+    }
+    
+    // A buffer that cannot even hold the tag
+    assert_misuse!(
+        ChachaPolyIetf::aead_cipher().seal(&mut buf[..15], 0, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: 16, got: 15 }
+    );
+}
+
+#[test]
+fn test_seal_buffer_one_byte_too_small() {
+    let (key, nonce) = (vec![0; 32], vec![0; 12]);
+    for &len in [0, 1, 15, 16, 17, 64, 65].iter() {
+        // Create a buffer that can hold the plaintext and all but one byte of the tag
+        let plaintext = vec![0x17; len];
+        let mut buf = vec![0; len + 15];
+        let expected = ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{
+            needed: len + 16, got: len + 15
+        });
+        
+        // Both sealing variants must fail cleanly instead of panicking
+        let error = ChachaPolyIetf.seal(&mut buf, len, &[], &key, &nonce)
+            .error_or("The operation must fail");
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&expected));
+        let error = ChachaPolyIetf.seal_to(&mut buf, &plaintext, &[], &key, &nonce)
+            .error_or("The operation must fail");
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&expected));
+    }
+}
+
+#[test]
+fn test_open_buffer_one_byte_too_small() {
+    let (key, nonce) = (vec![0; 32], vec![0; 12]);
+    for &len in [16, 17, 31, 32, 33, 80, 81].iter() {
+        // Create a buffer that can hold the plaintext and all but one byte of the tag
+        let mut buf = vec![0; len - 1];
+        let expected = ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{
+            needed: len, got: len - 1
+        });
+        
+        // Opening in place must fail cleanly instead of panicking
+        let error = ChachaPolyIetf.open(&mut buf, len, &[], &key, &nonce)
+            .error_or("The operation must fail");
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&expected));
+    }
+}
+
+
+#[test]
+fn test_oversized_buffer() {
+    let (key, nonce, plaintext) = (vec![0x07; 32], vec![0x0e; 12], vec![0x17; 100]);
+    let mut ciphertext = vec![0; plaintext.len() + 16];
+    ChachaPolyIetf.seal_to(&mut ciphertext, &plaintext, &[], &key, &nonce).unwrap();
+    
+    // Open into an oversized buffer; only the plaintext must be written
+    let mut buf = vec![0xAA; plaintext.len() + 64];
+    let len = ChachaPolyIetf.open_to(&mut buf, &ciphertext, &[], &key, &nonce).unwrap();
+    assert_eq!(&buf[..len], plaintext.as_slice());
+    assert!(buf[len..].iter().all(|b| *b == 0xAA));
+    
+    // A failed open must erase the written bytes and must not touch the tail
+    ciphertext[0] ^= 0x01;
+    let mut buf = vec![0xAA; plaintext.len() + 64];
+    ChachaPolyIetf.open_to(&mut buf, &ciphertext, &[], &key, &nonce).unwrap_err();
+    assert!(buf[..plaintext.len()].iter().all(|b| *b == 0x00));
+    assert!(buf[plaintext.len()..].iter().all(|b| *b == 0xAA));
+}
+
+#[test]
+fn test_tag_prefixed_buffer() {
+    let (key, nonce) = (vec![0; 32], vec![0; 12]);
+    let mut buf = vec![0; 20];
+    
+    // The buffer must hold the tag and the ciphertext
+    assert_eq!(
+        ChachaPolyIetf.seal_tag_prefixed(&mut buf[..19], b"Test", b"", &key, &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{ needed: 20, got: 19 }))
+    );
+    assert_eq!(ChachaPolyIetf.seal_tag_prefixed(&mut buf, b"Test", b"", &key, &nonce), Ok(20));
+    
+    // The output buffer must hold the plaintext and a ciphertext must contain at least the tag
+    let mut out = vec![0; 4];
+    assert_eq!(
+        ChachaPolyIetf.open_tag_prefixed(&mut out[..3], &buf, b"", &key, &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{ needed: 4, got: 3 }))
+    );
+    assert_eq!(
+        ChachaPolyIetf.open_tag_prefixed(&mut out, &buf[..15], b"", &key, &nonce),
+        Err(ChachaPolyError::TruncatedInput)
+    );
+    assert_eq!(ChachaPolyIetf.open_tag_prefixed(&mut out, &buf, b"", &key, &nonce), Ok(4));
+    assert_eq!(&out, b"Test");
+}
+
+
+#[test]
+fn test_empty_plaintext_and_ad() {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+    let (ad, plaintext) = (vec![0x17; 23], vec![0x2a; 37]);
+    
+    // All four combinations of empty/non-empty plaintext and AD must round-trip
+    for (ad, plaintext) in [(&[][..], &[][..]), (&ad, &[]), (&[], &plaintext), (&ad, &plaintext)] {
+        let mut sealed = vec![0; plaintext.len() + 16];
+        let len = ChachaPolyIetf.seal_to(&mut sealed, plaintext, ad, &key, &nonce).unwrap();
+        assert_eq!(len, plaintext.len() + 16);
+        
+        let mut buf = vec![0; plaintext.len()];
+        let len = ChachaPolyIetf.open_to(&mut buf, &sealed, ad, &key, &nonce).unwrap();
+        assert_eq!(&buf[..len], plaintext);
+        
+        // The AD must be authenticated even if the plaintext is empty
+        let other_ad = [ad, b"\x00"].concat();
+        ChachaPolyIetf.open_to(&mut buf, &sealed, &other_ad, &key, &nonce).unwrap_err();
+    }
+    
+    // With empty plaintext and AD, the tag is the Poly1305 MAC over the zero-length footer only
+    let mut pkey = vec![0; 32];
+    ChaCha20Ietf::keystream(&key, &nonce, 0, &mut pkey).unwrap();
+    let mut expected = vec![0; 16];
+    Poly1305::auth_raw(&mut expected, &[0; 16], &pkey, true).unwrap();
+    
+    let mut sealed = vec![0; 16];
+    ChachaPolyIetf.seal_to(&mut sealed, &[], &[], &key, &nonce).unwrap();
+    assert_eq!(sealed, expected);
+}
+
+
+#[test]
+fn test_vec() {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+    
+    // Seal grows the vector by the tag and open shrinks it again
+    for len in [0, 1, 64, 100] {
+        let plaintext = vec![0x2a; len];
+        let mut data = plaintext.clone();
+        ChachaPolyIetf.seal_vec(&mut data, b"AD", &key, &nonce).unwrap();
+        assert_eq!(data.len(), len + 16);
+        
+        ChachaPolyIetf.open_vec(&mut data, b"AD", &key, &nonce).unwrap();
+        assert_eq!(data, plaintext);
+    }
+    
+    // A failed open must leave the vector unchanged
+    let mut data = b"Test".to_vec();
+    ChachaPolyIetf.seal_vec(&mut data, b"", &key, &nonce).unwrap();
+    data[0] ^= 0x01;
+    let sealed = data.clone();
+    assert_eq!(
+        ChachaPolyIetf.open_vec(&mut data, b"", &key, &nonce),
+        Err(ChachaPolyError::InvalidData)
+    );
+    assert_eq!(data, sealed);
+    
+    // Too short inputs and invalid parameters must leave the vector unchanged
+    let mut data = vec![0x2a; 15];
+    assert_eq!(
+        ChachaPolyIetf.open_vec(&mut data, b"", &key, &nonce),
+        Err(ChachaPolyError::TruncatedInput)
+    );
+    assert_eq!(
+        ChachaPolyIetf.seal_vec(&mut data, b"", &key[..31], &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    assert_eq!(data, vec![0x2a; 15]);
+}
+
+
+#[test]
+fn test_split_tag() {
+    let ciphertext: Vec<u8> = (0..40).collect();
+    
+    // Too short inputs are truncated
+    assert_eq!(ChachaPolyIetf::split_tag(&[]), Err(ChachaPolyError::TruncatedInput));
+    assert_eq!(ChachaPolyIetf::split_tag(&ciphertext[..15]), Err(ChachaPolyError::TruncatedInput));
+    
+    // A tag without ciphertext
+    let (data, tag) = ChachaPolyIetf::split_tag(&ciphertext[..16]).unwrap();
+    assert!(data.is_empty());
+    assert_eq!(&tag[..], &ciphertext[..16]);
+    
+    // A ciphertext with tag
+    let (data, tag) = ChachaPolyIetf::split_tag(&ciphertext).unwrap();
+    assert_eq!(data, &ciphertext[..24]);
+    assert_eq!(&tag[..], &ciphertext[24..]);
+}
+
+#[test]
+fn test_deterministic() {
+    let key: Vec<u8> = (0..32).collect();
+    let seal = |plaintext: &[u8], ad: &[u8]| {
+        let mut buf = vec![0; 12 + plaintext.len() + 16];
+        let len = ChachaPolyIetf.seal_deterministic(&mut buf, plaintext, ad, &key).unwrap();
+        assert_eq!(len, buf.len());
+        buf
+    };
+    
+    // The same inputs must yield the same output and different inputs a different nonce
+    for &len in [0, 1, 16, 63, 64, 65, 300].iter() {
+        let plaintext = vec![0x17; len];
+        let sealed = seal(&plaintext, b"AD");
+        assert_eq!(sealed, seal(&plaintext, b"AD"));
+        assert_ne!(sealed[..12], seal(&plaintext, b"Ad")[..12]);
+        if len > 0 {
+            assert_ne!(sealed[..12], seal(&vec![0x18; len], b"AD")[..12]);
+        }
+        
+        // The output must be a regular ChachaPoly-IETF ciphertext under the prepended nonce
+        let mut buf = vec![0; len];
+        ChachaPolyIetf.open_to(&mut buf, &sealed[12..], b"AD", &key, &sealed[..12]).unwrap();
+        assert_eq!(buf, plaintext);
+        
+        // Round-trip and reject a modified nonce
+        let mut buf = vec![0; len];
+        assert_eq!(ChachaPolyIetf.open_deterministic(&mut buf, &sealed, b"AD", &key), Ok(len));
+        assert_eq!(buf, plaintext);
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 0x01;
+        assert_eq!(
+            ChachaPolyIetf.open_deterministic(&mut buf, &tampered, b"AD", &key),
+            Err(ChachaPolyError::InvalidData)
+        );
+    }
+    
+    // Invalid parameters
+    let mut buf = vec![0; 12 + 4 + 16];
+    assert_eq!(
+        ChachaPolyIetf.seal_deterministic(&mut buf[..31], b"Test", b"", &key),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{ needed: 32, got: 31 }))
+    );
+    assert_eq!(
+        ChachaPolyIetf.seal_deterministic(&mut buf, b"Test", b"", &key[..31]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    let sealed = seal(b"Test", b"");
+    assert_eq!(
+        ChachaPolyIetf.open_deterministic(&mut buf, &sealed[..27], b"", &key),
+        Err(ChachaPolyError::TruncatedInput)
+    );
+    assert_eq!(
+        ChachaPolyIetf.open_deterministic(&mut buf, &sealed[..11], b"", &key),
+        Err(ChachaPolyError::TruncatedInput)
+    );
+}
+
+#[test]
+fn test_deterministic_subkeys() {
+    /// Derives a subkey from `key` with `label` in place of the ChaCha20 constants
+    fn subkey(label: &[u8; 16], key: &[u8; 32]) -> [u8; 32] {
+        let words = |bytes: &[u8]| -> Vec<u32> {
+            bytes.chunks(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect()
+        };
+        let mut state = [0; 16];
+        state[..4].copy_from_slice(&words(label));
+        state[4..12].copy_from_slice(&words(key));
+        primitives::chacha20_permute(&mut state);
+        
+        let mut subkey = [0; 32];
+        let output = state[..4].iter().chain(&state[12..]).flat_map(|word| word.to_le_bytes());
+        subkey.iter_mut().zip(output).for_each(|(b, o)| *b = o);
+        subkey
+    }
+    
+    // The synthetic nonce must be derived from the labeled subkeys which are not ChaCha20 outputs
+    let key: [u8; 32] = (0..32).collect::<Vec<u8>>().try_into().unwrap();
+    let (plaintext, ad) = (b"Test data", b"AD");
+    let mut mac = primitives::Poly1305State::new(&subkey(b"chachasiv-mackey", &key));
+    mac.update(ad, false);
+    mac.update(plaintext, false);
+    let lengths = [ad.len() as u64, plaintext.len() as u64];
+    mac.update(&lengths.iter().flat_map(|len| len.to_le_bytes()).collect::<Vec<u8>>(), false);
+    let hash = mac.finish();
+    let nonce = primitives::hchacha20(&subkey(b"chachasiv-prfkey", &key), &hash);
+    
+    let mut buf = vec![0; 12 + plaintext.len() + 16];
+    ChachaPolyIetf.seal_deterministic(&mut buf, plaintext, ad, &key).unwrap();
+    assert_eq!(buf[..12], nonce[..12]);
+}
+
+#[test]
+fn test_remaining_capacity() {
+    #[cfg(target_pointer_width = "64")]
+    const MAX: usize = (4_294_967_296 - 1) * 64;
+    #[cfg(target_pointer_width = "32")]
+    const MAX: usize = usize::MAX - 16;
+    
+    assert_eq!(ChachaPolyIetf::remaining_capacity(0), MAX);
+    assert_eq!(ChachaPolyIetf::remaining_capacity(1), MAX - 1);
+    assert_eq!(ChachaPolyIetf::remaining_capacity(MAX - 1), 1);
+    assert_eq!(ChachaPolyIetf::remaining_capacity(MAX), 0);
+    assert_eq!(ChachaPolyIetf::remaining_capacity(MAX + 1), 0);
+    assert_eq!(ChachaPolyIetf::remaining_capacity(usize::MAX), 0);
+}
+
+#[test]
+fn test_ad_reader() {
+    /// A reader that returns at most 7 bytes per call to exercise short reads
+    struct SlowReader<'a>(&'a [u8]);
+    impl<'a> Read for SlowReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+    
+    let (key, nonce, plaintext) = (vec![0x07; 32], vec![0x0e; 12], vec![0x17; 100]);
+    for &ad_len in [0, 1, 15, 16, 4095, 4096, 4097, 8192, 10_005].iter() {
+        let ad: Vec<u8> = (0..ad_len).map(|i| i as u8).collect();
+        
+        // Seal with the AD in memory
+        let mut expected = vec![0; plaintext.len() + 16];
+        ChachaPolyIetf.seal_to(&mut expected, &plaintext, &ad, &key, &nonce).unwrap();
+        
+        // Seal with the AD from a cursor and a slow reader
+        let mut sealed = vec![0; plaintext.len() + 16];
+        ChachaPolyIetf.seal_to_with_ad_reader(&mut sealed, &plaintext, &mut Cursor::new(&ad), &key,
+            &nonce).unwrap();
+        assert_eq!(sealed, expected, "AD length: {}", ad_len);
+        ChachaPolyIetf.seal_to_with_ad_reader(&mut sealed, &plaintext, &mut SlowReader(&ad), &key,
+            &nonce).unwrap();
+        assert_eq!(sealed, expected, "AD length: {}", ad_len);
+        
+        // Open with the AD from a cursor
+        let mut buf = vec![0; plaintext.len()];
+        let len = ChachaPolyIetf.open_to_with_ad_reader(&mut buf, &sealed, &mut Cursor::new(&ad),
+            &key, &nonce).unwrap();
+        assert_eq!(&buf[..len], plaintext.as_slice(), "AD length: {}", ad_len);
+        
+        // Open with a modified AD
+        let mut tampered = ad.clone();
+        tampered.push(0x00);
+        let error = ChachaPolyIetf.open_to_with_ad_reader(&mut buf, &sealed,
+            &mut Cursor::new(&tampered), &key, &nonce).unwrap_err();
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&ChachaPolyError::InvalidData));
+    }
+}
+
+#[test]
+fn test_open_ct() {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+    for &len in [0, 1, 63, 64, 65, 300].iter() {
+        let plaintext = vec![0x17; len];
+        let mut sealed = vec![0; len + 16];
+        ChachaPolyIetf.seal_to(&mut sealed, &plaintext, b"AD", &key, &nonce).unwrap();
+        
+        // A valid ciphertext writes the plaintext and leaves the tail untouched
+        let mut buf = vec![0xAA; len + 8];
+        assert_eq!(ChachaPolyIetf.open_ct(&mut buf, &sealed, b"AD", &key, &nonce), Ok(len));
+        assert_eq!(&buf[..len], plaintext.as_slice());
+        assert!(buf[len..].iter().all(|b| *b == 0xAA));
+        
+        // An invalid ciphertext writes exactly the same bytes but they contain no plaintext
+        for &pos in [0, len / 2, len + 15].iter().filter(|&&pos| pos < len + 16) {
+            let mut tampered = sealed.clone();
+            tampered[pos] ^= 0x01;
+            let mut buf = vec![0xAA; len + 8];
+            assert_eq!(
+                ChachaPolyIetf.open_ct(&mut buf, &tampered, b"AD", &key, &nonce),
+                Err(ChachaPolyError::InvalidData)
+            );
+            assert!(buf[..len].iter().all(|b| *b == 0x00), "Length: {}, position: {}", len, pos);
+            assert!(buf[len..].iter().all(|b| *b == 0xAA), "Length: {}, position: {}", len, pos);
+        }
+    }
+    
+    // Invalid parameters
+    let mut buf = vec![0; 4];
+    assert_eq!(
+        ChachaPolyIetf.open_ct(&mut buf, &[0; 15], b"", &key, &nonce),
+        Err(ChachaPolyError::TruncatedInput)
+    );
+    assert_eq!(
+        ChachaPolyIetf.open_ct(&mut buf[..3], &[0; 20], b"", &key, &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{ needed: 4, got: 3 }))
+    );
+}
+
+#[test]
+fn test_etm() {
+    let (enc_key, mac_key, nonce) = (vec![0x07; 32], vec![0x70; 32], vec![0x0e; 12]);
+    for &len in [0, 1, 64, 65, 300].iter() {
+        let plaintext = vec![0x17; len];
+        let mut sealed = vec![0; len + 16];
+        let sealed_len = ChachaPolyIetf
+            .seal_etm(&mut sealed, &plaintext, b"AD", &enc_key, &mac_key, &nonce).unwrap();
+        assert_eq!(sealed_len, len + 16);
+        
+        // The ciphertext is the ChaCha20-IETF encryption under `enc_key` starting at block 1
+        let mut ciphertext = plaintext.clone();
+        ChaCha20Ietf::xor(&enc_key, &nonce, 1, &mut ciphertext);
+        assert_eq!(&sealed[..len], ciphertext.as_slice());
+        
+        // Round-trip
+        let mut buf = vec![0; len];
+        let opened = ChachaPolyIetf.open_etm(&mut buf, &sealed, b"AD", &enc_key, &mac_key, &nonce);
+        assert_eq!(opened, Ok(len));
+        assert_eq!(buf, plaintext);
+        
+        // Tampered data, swapped keys and a regular open must fail
+        let mut tampered = sealed.clone();
+        tampered[len / 2] ^= 0x01;
+        assert_eq!(
+            ChachaPolyIetf.open_etm(&mut buf, &tampered, b"AD", &enc_key, &mac_key, &nonce),
+            Err(ChachaPolyError::InvalidData)
+        );
+        assert_eq!(
+            ChachaPolyIetf.open_etm(&mut buf, &sealed, b"Ad", &enc_key, &mac_key, &nonce),
+            Err(ChachaPolyError::InvalidData)
+        );
+        assert_eq!(
+            ChachaPolyIetf.open_etm(&mut buf, &sealed, b"AD", &mac_key, &enc_key, &nonce),
+            Err(ChachaPolyError::InvalidData)
+        );
+        assert!(ChachaPolyIetf.open_to(&mut buf, &sealed, b"AD", &enc_key, &nonce).is_err());
+        
+        // With identical keys, it is the regular ChachaPoly-IETF
+        let mut expected = vec![0; len + 16];
+        ChachaPolyIetf.seal_to(&mut expected, &plaintext, b"AD", &enc_key, &nonce).unwrap();
+        ChachaPolyIetf
+            .seal_etm(&mut sealed, &plaintext, b"AD", &enc_key, &enc_key, &nonce).unwrap();
+        assert_eq!(sealed, expected);
+    }
+    
+    // Invalid MAC key
+    let mut buf = vec![0; 20];
+    assert_eq!(
+        ChachaPolyIetf.seal_etm(&mut buf, b"Test", b"", &enc_key, &mac_key[..31], &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+}
+
+#[test]
+fn test_supports_tag_len() {
+    assert!(ChachaPolyIetf::supports_tag_len(16));
+    for &len in [0, 1, 4, 8, 12, 15, 17, 32, usize::MAX].iter() {
+        assert!(!ChachaPolyIetf::supports_tag_len(len), "Length: {}", len);
+    }
+}
+
+#[test]
+fn test_open_with_ad_out() {
+    let (key, nonce, plaintext) = (vec![0x07; 32], vec![0x0e; 12], b"Test".to_vec());
+    let mut sealed = vec![0; plaintext.len() + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, &plaintext, b"Header 1", &key, &nonce).unwrap();
+    
+    // The expected AD is the embedded AD
+    let mut buf = vec![0; plaintext.len()];
+    assert_eq!(
+        ChachaPolyIetf.open_with_ad_out(&mut buf, &sealed, b"Header 1", b"Header 1", &key, &nonce),
+        Ok(4)
+    );
+    assert_eq!(buf, plaintext);
+    
+    // The tag is valid for the embedded AD but the application expects another AD
+    let mut buf = vec![0; plaintext.len()];
+    assert_eq!(
+        ChachaPolyIetf.open_with_ad_out(&mut buf, &sealed, b"Header 1", b"Header 2", &key, &nonce),
+        Err(ChachaPolyError::InvalidData)
+    );
+    assert_eq!(buf, vec![0; plaintext.len()]);
+    assert_eq!(
+        ChachaPolyIetf.open_with_ad_out(&mut buf, &sealed, b"Header 1", b"Header", &key, &nonce),
+        Err(ChachaPolyError::InvalidData)
+    );
+    
+    // A modified embedded AD is still caught by the tag
+    assert_eq!(
+        ChachaPolyIetf.open_with_ad_out(&mut buf, &sealed, b"Header 2", b"Header 2", &key, &nonce),
+        Err(ChachaPolyError::InvalidData)
+    );
+}
+
+#[test]
+fn test_open_framed_with_nonce() {
+    let (key, nonce, magic) = (vec![0x07; 32], vec![0x0e; 12], b"CPv1");
+    
+    // Assemble a valid frame
+    let mut frame = magic.to_vec();
+    frame.extend_from_slice(&nonce);
+    let mut sealed = vec![0; 4 + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, b"Test", b"AD", &key, &nonce).unwrap();
+    frame.extend_from_slice(&sealed);
+    assert_eq!(
+        ChachaPolyIetf.open_framed_with_nonce(&frame, magic, b"AD", &key),
+        Ok(b"Test".to_vec())
+    );
+    
+    // Malformed headers
+    let mut invalid_magic = frame.clone();
+    invalid_magic[3] = b'2';
+    assert_eq!(
+        ChachaPolyIetf.open_framed_with_nonce(&invalid_magic, magic, b"AD", &key),
+        Err(ChachaPolyError::ApiMisuse(Misuse::InvalidMagic))
+    );
+    for &len in [0, 3, 4, 15, 16, 31].iter() {
+        assert_eq!(
+            ChachaPolyIetf.open_framed_with_nonce(&frame[..len], magic, b"AD", &key),
+            Err(ChachaPolyError::TruncatedInput), "Length: {}", len
+        );
+    }
+    
+    // A modified nonce, ciphertext or AD
+    for &pos in [4, 15, 16, 35].iter() {
+        let mut tampered = frame.clone();
+        tampered[pos] ^= 0x01;
+        assert_eq!(
+            ChachaPolyIetf.open_framed_with_nonce(&tampered, magic, b"AD", &key),
+            Err(ChachaPolyError::InvalidData), "Position: {}", pos
+        );
+    }
+    assert_eq!(
+        ChachaPolyIetf.open_framed_with_nonce(&frame, magic, b"Ad", &key),
+        Err(ChachaPolyError::InvalidData)
+    );
+}
+
+#[test]
+fn test_rekey() {
+    let (key, nonce) = ([0x42; 32], [0x07; 12]);
+    
+    // Rekeying is deterministic and yields a different key
+    let new_key = ChachaPolyIetf::rekey(&key, &nonce);
+    assert_eq!(new_key, ChachaPolyIetf::rekey(&key, &nonce));
+    assert_ne!(new_key, key);
+    assert_ne!(ChachaPolyIetf::rekey(&new_key, &nonce), new_key);
+    assert_ne!(ChachaPolyIetf::rekey(&key, &[0x08; 12]), new_key);
+    
+    // The new key is the second half of keystream block 0
+    let mut block = vec![0; 64];
+    ChaCha20Ietf::keystream(&key, &nonce, 0, &mut block).unwrap();
+    assert_eq!(new_key, block[32..]);
+}
+
+#[test]
+fn test_validate_params() {
+    let params = [
+        (32, 12, 0, 16), (32, 12, 7, 23), (32, 12, 7, 100), (32, 12, 7, 22), (32, 12, 0, 15),
+        (31, 12, 7, 23), (33, 12, 7, 23), (32, 11, 7, 23), (32, 24, 7, 23), (0, 0, 7, 0)
+    ];
+    for &param in params.iter() {
+        let (key_len, nonce_len, plaintext_len, buf_len) = param;
+        // Seal with buffers of the given lengths
+        let (key, nonce) = (vec![0; key_len], vec![0; nonce_len]);
+        let (plaintext, mut buf) = (vec![0; plaintext_len], vec![0; buf_len]);
+        let sealed = ChachaPolyIetf.seal_to(&mut buf, &plaintext, b"", &key, &nonce)
+            .map_err(|e| *e.downcast::<ChachaPolyError>().unwrap());
+        
+        // `validate_params` must agree with `seal_to`
+        let validated = ChachaPolyIetf::validate_params(key_len, nonce_len, plaintext_len, buf_len);
+        assert_eq!(validated, sealed.map(|_| ()), "Parameters: {:?}", param);
+    }
+    
+    // Lengths that cannot be allocated
+    assert_eq!(
+        ChachaPolyIetf::validate_params(32, 12, usize::MAX, usize::MAX),
+        Err(ChachaPolyError::ApiMisuse(Misuse::TooMuchData))
+    );
+}
+
+#[test]
+fn test_seal_split() {
+    let (key, nonce, plaintext) = ([0x33; 32], [0x44; 12], b"Split body".to_vec());
+    let mut expected = vec![0; plaintext.len() + 16];
+    ChachaPolyIetf.seal_to(&mut expected, &plaintext, b"AD", &key, &nonce).unwrap();
+    
+    // Carve the tag (header) and the body from the same allocation
+    let mut frame = vec![0; 4 + 16 + plaintext.len()];
+    let (header, body) = frame.split_at_mut(4 + 16);
+    let tag: &mut [u8; 16] = (&mut header[4..]).try_into().unwrap();
+    ChachaPolyIetf.seal_split(body, tag, &plaintext, b"AD", &key, &nonce).unwrap();
+    assert_eq!(&frame[..4], &[0; 4]);
+    assert_eq!(&frame[4..20], &expected[plaintext.len()..]);
+    assert_eq!(&frame[20..], &expected[..plaintext.len()]);
+    
+    // The body must have the exact size
+    let (mut body, mut tag) = (vec![0; plaintext.len() + 1], [0; 16]);
+    assert_eq!(
+        ChachaPolyIetf.seal_split(&mut body, &mut tag, &plaintext, b"AD", &key, &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferLen))
+    );
+    assert_eq!(
+        ChachaPolyIetf.seal_split(&mut body[..9], &mut tag, &plaintext, b"AD", &key, &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{ needed: 10, got: 9 }))
+    );
+    assert_eq!((body, tag), (vec![0; plaintext.len() + 1], [0; 16]));
+}
+
+
+#[test]
+fn test_ad_max() {
+    // AD lengths whose 64 bit little-endian encoding spans multiple bytes
+    let (key, nonce) = ([0x17; 32], [0x2a; 12]);
+    let lengths: [(usize, [u8; 8]); 4] = [
+        (0x01_00, [0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+        (0x01_02_03, [0x03, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]),
+        (0x01_00_00_00, [0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]),
+        (0x01_00_00_07, [0x07, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00])
+    ];
+    for (len, encoded) in lengths.iter() {
+        let (ad, mut sealed) = (vec![0x07; *len], vec![0; 16]);
+        ChachaPolyIetf.seal_to(&mut sealed, b"", &ad, &key, &nonce).unwrap();
+        
+        // The tag must match the MAC over `ad || pad || le64(ad.len()) || le64(0)`
+        let mut pkey = [0; 32];
+        ChaCha20Ietf::keystream(&key, &nonce, 0, &mut pkey).unwrap();
+        let mut mac_data = ad;
+        mac_data.resize(len.div_ceil(16) * 16, 0);
+        mac_data.extend_from_slice(encoded);
+        mac_data.extend_from_slice(&[0; 8]);
+        let mut tag = [0; 16];
+        Poly1305::auth_raw(&mut tag, &mac_data, &pkey, true).unwrap();
+        assert_eq!(sealed, tag, "AD length: {}", len);
+    }
+}
+
+#[test]
+fn test_arr() {
+    let (key, nonce) = ([0x17; 32], [0x2a; 12]);
+    for &len in [0, 1, 15, 16, 17, 64, 300].iter() {
+        // Sealing must match the slice variant
+        let plaintext = vec![0x07; len];
+        let (mut sealed, mut sealed_arr) = (vec![0; len + 16], vec![0; len + 17]);
+        ChachaPolyIetf.seal_to(&mut sealed, &plaintext, b"AD", &key, &nonce).unwrap();
+        let sealed_len = ChachaPolyIetf
+            .seal_to_arr(&mut sealed_arr, &plaintext, b"AD", &key, &nonce).unwrap();
+        assert_eq!(sealed, &sealed_arr[..sealed_len], "Length: {}", len);
+        
+        // Opening must match the slice variant
+        let mut buf = vec![0; len];
+        assert_eq!(ChachaPolyIetf.open_to_arr(&mut buf, &sealed, b"AD", &key, &nonce), Ok(len));
+        assert_eq!(buf, plaintext, "Length: {}", len);
+    }
+    
+    // The errors must match the slice variants
+    let mut sealed = vec![0; 32];
+    ChachaPolyIetf.seal_to(&mut sealed, &[0; 16], b"AD", &key, &nonce).unwrap();
+    let cases: &[(usize, &[u8], &[u8], bool)] = &[
+        (31, &[0; 16], b"", true), (15, &sealed, b"AD", false),
+        (16, &sealed[..15], b"AD", false), (16, &sealed, b"Ad", false)
+    ];
+    for &(buf_len, data, ad, seal) in cases.iter() {
+        let mut buf = vec![0; buf_len];
+        let (error, error_arr) = match seal {
+            true => (
+                ChachaPolyIetf.seal_to(&mut buf, data, ad, &key, &nonce).unwrap_err(),
+                ChachaPolyIetf.seal_to_arr(&mut buf, data, ad, &key, &nonce).unwrap_err()
+            ),
+            false => (
+                ChachaPolyIetf.open_to(&mut buf, data, ad, &key, &nonce).unwrap_err(),
+                ChachaPolyIetf.open_to_arr(&mut buf, data, ad, &key, &nonce).unwrap_err()
+            )
+        };
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&error_arr));
+    }
+}
+
+#[test]
+fn test_open_framed_parts() {
+    let (key, nonce, ad, plaintext) = ([0x17; 32], [0x2a; 12], b"Header", b"Framed payload");
+    
+    // Assemble the frame (`le64(ad.len()) || ad || ciphertext || tag`)
+    let mut frame = (ad.len() as u64).to_le_bytes().to_vec();
+    frame.extend_from_slice(ad);
+    let mut sealed = vec![0; plaintext.len() + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, plaintext, ad, &key, &nonce).unwrap();
+    frame.extend_from_slice(&sealed);
+    
+    // The returned range must slice the AD from the frame
+    let mut buf = vec![0; frame.len()];
+    let (len, ad_range) = ChachaPolyIetf.open_framed_parts(&mut buf, &frame, &key, &nonce).unwrap();
+    assert_eq!(&buf[..len], plaintext);
+    assert_eq!(&frame[ad_range], ad);
+    
+    // An empty AD
+    let mut empty_frame = vec![0; 8];
+    let mut sealed = vec![0; plaintext.len() + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, plaintext, b"", &key, &nonce).unwrap();
+    empty_frame.extend_from_slice(&sealed);
+    let (len, ad_range) = ChachaPolyIetf.open_framed_parts(&mut buf, &empty_frame, &key, &nonce)
+        .unwrap();
+    assert_eq!((&buf[..len], ad_range), (&plaintext[..], 8..8));
+    
+    // Tampering with the AD length, the AD, the ciphertext or the tag
+    for pos in (0..1).chain(8..frame.len()) {
+        let mut tampered = frame.clone();
+        tampered[pos] ^= 0x01;
+        assert_eq!(
+            ChachaPolyIetf.open_framed_parts(&mut buf, &tampered, &key, &nonce),
+            Err(ChachaPolyError::InvalidData), "Position: {}", pos
+        );
+    }
+    
+    // An AD length beyond the frame or a truncated frame
+    for pos in 1..8 {
+        let mut tampered = frame.clone();
+        tampered[pos] ^= 0x01;
+        assert_eq!(
+            ChachaPolyIetf.open_framed_parts(&mut buf, &tampered, &key, &nonce),
+            Err(ChachaPolyError::TruncatedInput), "Position: {}", pos
+        );
+    }
+    for &len in [0, 7, 8, 13, 14, 29].iter() {
+        assert_eq!(
+            ChachaPolyIetf.open_framed_parts(&mut buf, &frame[..len], &key, &nonce),
+            Err(ChachaPolyError::TruncatedInput), "Length: {}", len
+        );
+    }
+}
+
+#[test]
+fn test_verify_log() {
+    let key = [0x17; 32];
+    
+    // Create the log
+    let mut log = Vec::new();
+    for i in 0..5u8 {
+        let (ad, plaintext, nonce) = (vec![i; i as usize], vec![i; 10 * i as usize], [i; 12]);
+        let mut sealed = vec![0; plaintext.len() + 16];
+        ChachaPolyIetf.seal_to(&mut sealed, &plaintext, &ad, &key, &nonce).unwrap();
+        log.push((ad, sealed, nonce));
+    }
+    fn records(log: &[(Vec<u8>, Vec<u8>, [u8; 12])])
+        -> impl Iterator<Item = (&[u8], &[u8], &[u8; 12])>
+    {
+        log.iter().map(|(ad, sealed, nonce)| (ad.as_slice(), sealed.as_slice(), nonce))
+    }
+    
+    // A clean log
+    assert_eq!(ChachaPolyIetf.verify_log(records(&log), &key), Ok(5));
+    assert_eq!(ChachaPolyIetf.verify_log(records(&log[..0]), &key), Ok(0));
+    
+    // A corrupted record in the middle, a truncated record and a record with a wrong nonce
+    let mut corrupted = log.clone();
+    corrupted[2].1[3] ^= 0x01;
+    assert_eq!(
+        ChachaPolyIetf.verify_log(records(&corrupted), &key),
+        Err((2, ChachaPolyError::InvalidData))
+    );
+    let mut truncated = log.clone();
+    truncated[3].1.truncate(15);
+    assert_eq!(
+        ChachaPolyIetf.verify_log(records(&truncated), &key),
+        Err((3, ChachaPolyError::TruncatedInput))
+    );
+    let mut mismatched = log.clone();
+    mismatched[1].2 = log[4].2;
+    assert_eq!(
+        ChachaPolyIetf.verify_log(records(&mismatched), &key),
+        Err((1, ChachaPolyError::InvalidData))
+    );
+    
+    // An invalid key
+    assert_eq!(
+        ChachaPolyIetf.verify_log(records(&log), &key[..31]),
+        Err((0, ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 })))
+    );
+}
@@ -0,0 +1,40 @@
+use crypto_api_chachapoly::{
+    CipherExt, ChaCha20Ietf, ChachaPolyIetf, ChachaPolyError, Misuse, crypto_api::cipher::Cipher
+};
+
+
+#[test]
+fn test_boxed_cipher() {
+    let (key, nonce): (Vec<u8>, Vec<u8>) = ((0..32).collect(), (0..12).collect());
+    let plaintext: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    
+    // Encrypt and decrypt through the trait object
+    let cipher: Box<dyn Cipher> = ChaCha20Ietf::cipher();
+    let ciphertext = cipher.encrypt_vec(&plaintext, &key, &nonce).unwrap();
+    let mut expected = plaintext.clone();
+    ChaCha20Ietf::xor(&key, &nonce, 0, &mut expected);
+    assert_eq!(ciphertext, expected);
+    assert_eq!(cipher.decrypt_vec(&ciphertext, &key, &nonce).unwrap(), plaintext);
+    
+    // The ciphertext is truncated to the actual length
+    let cipher = ChachaPolyIetf::cipher();
+    let ciphertext = cipher.encrypt_vec(&plaintext, &key, &nonce).unwrap();
+    assert_eq!(ciphertext.len(), plaintext.len() + 16);
+    assert_eq!(cipher.decrypt_vec(&ciphertext, &key, &nonce).unwrap(), plaintext);
+    assert_eq!(cipher.encrypt_vec(b"", &key, &nonce).unwrap().len(), 16);
+}
+
+
+#[test]
+fn test_errors() {
+    let cipher = ChaCha20Ietf::cipher();
+    let error = cipher.encrypt_vec(b"Test", &[0; 31], &[0; 12]).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<ChachaPolyError>(),
+        Some(&ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    
+    let cipher = ChachaPolyIetf::cipher();
+    let error = cipher.decrypt_vec(&[0; 16], &[0; 32], &[0; 12]).unwrap_err();
+    assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&ChachaPolyError::InvalidData));
+}
\ No newline at end of file
@@ -0,0 +1,109 @@
+// Timing measurements of unoptimized builds are meaningless
+#![cfg(all(feature = "ct-tests", not(debug_assertions)))]
+
+use crypto_api_chachapoly::{ ChachaPolyIetf, Tag, crypto_api::cipher::AeadCipher };
+use std::{
+    hint::black_box, time::Instant,
+    sync::Mutex
+};
+
+
+/// The amount of measurements per class
+const MEASUREMENTS: usize = 200_000;
+/// The amount of repetitions per measurement to lift short operations above the timer resolution
+const REPETITIONS: usize = 16;
+/// The percentile above which measurements are discarded as outliers (e.g. due to interrupts)
+const CROP_PERCENTILE: f64 = 0.9;
+/// The maximum absolute t-value that is accepted (dudect considers `> 10` as definitely leaky)
+const T_THRESHOLD: f64 = 10.0;
+
+
+/// A deterministic xorshift RNG to interleave the measurement classes
+struct XorShift(u64);
+impl XorShift {
+    /// Returns the next random bit
+    pub fn next_bit(&mut self) -> bool {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 & 1 == 1
+    }
+}
+
+
+/// Computes Welch's t-statistic for the cropped measurements of `a` and `b`
+fn welch_t(mut a: Vec<f64>, mut b: Vec<f64>) -> f64 {
+    /// Crops `values` above the percentile and returns the amount, the mean and the variance
+    fn stats(values: &mut Vec<f64>) -> (f64, f64, f64) {
+        values.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        values.truncate((values.len() as f64 * CROP_PERCENTILE) as usize);
+        
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0);
+        (n, mean, var)
+    }
+    
+    let ((n_a, mean_a, var_a), (n_b, mean_b, var_b)) = (stats(&mut a), stats(&mut b));
+    (mean_a - mean_b) / (var_a / n_a + var_b / n_b).sqrt()
+}
+
+
+/// Serializes the measurements so that parallel tests don't disturb each other
+static LOCK: Mutex<()> = Mutex::new(());
+
+
+/// Measures `f` for both classes in a random interleaving and returns the t-statistic
+fn measure<F: FnMut(bool)>(mut f: F) -> f64 {
+    let _lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (mut rng, mut a, mut b) = (XorShift(0x5eed), Vec::new(), Vec::new());
+    while a.len() < MEASUREMENTS || b.len() < MEASUREMENTS {
+        let class = rng.next_bit();
+        let start = Instant::now();
+        (0..REPETITIONS).for_each(|_| f(class));
+        let elapsed = start.elapsed().as_nanos() as f64;
+        
+        match class {
+            true if a.len() < MEASUREMENTS => a.push(elapsed),
+            false if b.len() < MEASUREMENTS => b.push(elapsed),
+            _ => continue
+        }
+    }
+    welch_t(a, b)
+}
+
+
+#[test]
+fn test_tag_eq() {
+    let tag = Tag([0x5a; 16]);
+    let (mut early, mut late) = (tag, tag);
+    early.0[0] ^= 0x01;
+    late.0[15] ^= 0x01;
+    
+    let t = measure(|class| {
+        let other = match class { true => &early, false => &late };
+        black_box(black_box(&tag) == black_box(other));
+    });
+    assert!(t.abs() < T_THRESHOLD, "Tag comparison timing leaks (t = {})", t);
+}
+
+
+#[test]
+fn test_open_tag_mismatch() {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+    let mut sealed = vec![0; 64 + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, &[0x17; 64], b"", &key, &nonce).unwrap();
+    
+    // Create a ciphertext with an early and one with a late tag mismatch
+    let (mut early, mut late) = (sealed.clone(), sealed);
+    early[64] ^= 0x01;
+    late[79] ^= 0x01;
+    
+    let mut buf = vec![0; 64];
+    let t = measure(|class| {
+        let ciphertext = match class { true => &early, false => &late };
+        let result = ChachaPolyIetf.open_to(&mut buf, black_box(ciphertext), b"", &key, &nonce);
+        black_box(result).unwrap_err();
+    });
+    assert!(t.abs() < T_THRESHOLD, "Open timing leaks (t = {})", t);
+}
\ No newline at end of file
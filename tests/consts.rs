@@ -0,0 +1,40 @@
+use crypto_api_chachapoly::{
+    XChachaPoly, ChachaPolyIetf, ChaCha20Ietf, Poly1305,
+    consts::{
+        CHACHA20_KEY, CHACHA20_NONCE, CHACHA20_ORIG_NONCE, XCHACHA20_NONCE, POLY1305_KEY,
+        POLY1305_TAG, CHACHAPOLY_KEY, CHACHAPOLY_NONCE, CHACHAPOLY_TAG, CHACHAPOLY_ORIG_NONCE,
+        XCHACHAPOLY_KEY, XCHACHAPOLY_NONCE, XCHACHAPOLY_TAG, CHACHAPOLY_STREAM_PREFIX,
+        CHACHAPOLY_WRAPPED_CEK
+    },
+    crypto_api::cipher::AeadCipher
+};
+
+
+#[test]
+fn test_values() {
+    assert_eq!((CHACHA20_KEY, CHACHA20_NONCE, CHACHA20_ORIG_NONCE), (32, 12, 8));
+    assert_eq!(XCHACHA20_NONCE, 24);
+    assert_eq!((POLY1305_KEY, POLY1305_TAG), (32, 16));
+    assert_eq!((CHACHAPOLY_KEY, CHACHAPOLY_NONCE, CHACHAPOLY_TAG), (32, 12, 16));
+    assert_eq!(CHACHAPOLY_ORIG_NONCE, 8);
+    assert_eq!((XCHACHAPOLY_KEY, XCHACHAPOLY_NONCE, XCHACHAPOLY_TAG), (32, 24, 16));
+    assert_eq!((CHACHAPOLY_STREAM_PREFIX, CHACHAPOLY_WRAPPED_CEK), (7, 60));
+}
+
+
+#[test]
+fn test_buffer_sizing() {
+    // Size all buffers via the constants
+    let (key, nonce) = ([0x17; XCHACHAPOLY_KEY], [0x2a; XCHACHAPOLY_NONCE]);
+    let mut sealed = vec![0; 5 + XCHACHAPOLY_TAG];
+    XChachaPoly.seal_to(&mut sealed, b"Sized", b"", &key, &nonce).unwrap();
+    
+    let (key, nonce) = ([0x17; CHACHAPOLY_KEY], [0x2a; CHACHAPOLY_NONCE]);
+    let mut sealed = vec![0; 5 + CHACHAPOLY_TAG];
+    ChachaPolyIetf.seal_to(&mut sealed, b"Sized", b"", &key, &nonce).unwrap();
+    
+    let mut buf = vec![0; 5];
+    ChaCha20Ietf::cipher().encrypt_to(&mut buf, b"Sized", &key, &nonce).unwrap();
+    let mut tag = vec![0; POLY1305_TAG];
+    Poly1305::mac().auth(&mut tag, b"Sized", &[0x07; POLY1305_KEY]).unwrap();
+}
\ No newline at end of file
@@ -0,0 +1,42 @@
+#![cfg(feature = "debug-trace")]
+
+use crypto_api_chachapoly::{ ChachaPolyIetf, ChachaPolyError, Misuse };
+
+
+#[test]
+fn test_rfc8439_2_8_2() {
+    // The AEAD example from RFC 8439 section 2.8.2
+    let key: Vec<u8> = (0x80..0xa0).collect();
+    let nonce = hex::decode("070000004041424344454647").unwrap();
+    let ad = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+    let plaintext = concat!(
+        "Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the ",
+        "future, sunscreen would be it."
+    ).as_bytes();
+    
+    let trace = ChachaPolyIetf::debug_trace(plaintext, &ad, &key, &nonce).unwrap();
+    assert_eq!(
+        trace.poly_key.to_vec(),
+        hex::decode("7bac2b252db447af09b67a55a4e955840ae1d6731075d9eb2a9375783ed553ff").unwrap()
+    );
+    assert_eq!(trace.ciphertext, hex::decode(concat!(
+        "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69",
+        "da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad67594",
+        "5585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116"
+    )).unwrap());
+    assert_eq!(trace.footer.to_vec(), hex::decode("0c000000000000007200000000000000").unwrap());
+    assert_eq!(trace.tag.to_vec(), hex::decode("1ae10b594f09e26a7e902ecbd0600691").unwrap());
+}
+
+
+#[test]
+fn test_api() {
+    assert_eq!(
+        ChachaPolyIetf::debug_trace(b"", b"", &[0; 31], &[0; 12]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    assert_eq!(
+        ChachaPolyIetf::debug_trace(b"", b"", &[0; 32], &[0; 13]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 12, got: 13 }))
+    );
+}
\ No newline at end of file
@@ -0,0 +1,76 @@
+use crypto_api_chachapoly::{ ChachaPolyIetf, crypto_api::rng::SecureRng };
+use std::error::Error;
+
+
+/// A deterministic (and thus insecure!) RNG that produces a different byte sequence on each call
+struct CounterRng(u8);
+impl SecureRng for CounterRng {
+    fn random(&mut self, buf: &mut[u8]) -> Result<(), Box<dyn Error + 'static>> {
+        buf.iter_mut().for_each(|b| { *b = self.0; self.0 = self.0.wrapping_add(1) });
+        Ok(())
+    }
+}
+
+
+#[test]
+fn test_multi_recipient() {
+    let (plaintext, ad) = (b"Multi-recipient payload".to_vec(), b"Header".to_vec());
+    let keys = [[0x01; 32], [0x02; 32], [0x03; 32]];
+    let (recipient_keys, mut rng): (Vec<&[u8; 32]>, _) = (keys.iter().collect(), CounterRng(0));
+    
+    // Seal the payload
+    let mut ciphertext = vec![0; plaintext.len() + 16];
+    let (len, wrapped_ceks) = ChachaPolyIetf
+        .seal_with_random_cek(&mut ciphertext, &plaintext, &ad, &recipient_keys, &mut rng)
+        .unwrap();
+    assert_eq!(len, ciphertext.len());
+    assert_eq!(wrapped_ceks.len(), keys.len());
+    
+    // Each recipient can open the payload with its wrapped CEK
+    for (key, wrapped_cek) in keys.iter().zip(wrapped_ceks.iter()) {
+        let mut buf = vec![0; plaintext.len()];
+        let len = ChachaPolyIetf
+            .open_with_wrapped_cek(&mut buf, &ciphertext, &ad, wrapped_cek, key)
+            .unwrap();
+        assert_eq!(&buf[..len], plaintext.as_slice());
+    }
+    
+    // A recipient cannot use another recipient's wrapped CEK
+    let mut buf = vec![0; plaintext.len()];
+    let error = ChachaPolyIetf
+        .open_with_wrapped_cek(&mut buf, &ciphertext, &ad, &wrapped_ceks[1], &keys[0])
+        .unwrap_err();
+    assert_eq!(error.to_string(), "InvalidData");
+}
+
+#[test]
+fn test_wrapped_cek_binding() {
+    let keys = [[0x01; 32], [0x02; 32]];
+    let (recipient_keys, mut rng): (Vec<&[u8; 32]>, _) = (keys.iter().collect(), CounterRng(0));
+    
+    // Seal two envelopes for the same recipients
+    let (mut first, mut second) = (vec![0; 5 + 16], vec![0; 6 + 16]);
+    let (_, first_ceks) = ChachaPolyIetf
+        .seal_with_random_cek(&mut first, b"First", b"", &recipient_keys, &mut rng)
+        .unwrap();
+    let (_, second_ceks) = ChachaPolyIetf
+        .seal_with_random_cek(&mut second, b"Second", b"", &recipient_keys, &mut rng)
+        .unwrap();
+    
+    // A wrapped CEK cannot be swapped into another envelope
+    let mut buf = vec![0; 6];
+    let error = ChachaPolyIetf
+        .open_with_wrapped_cek(&mut buf, &second, b"", &first_ceks[0], &keys[0])
+        .unwrap_err();
+    assert_eq!(error.to_string(), "InvalidData");
+    let len = ChachaPolyIetf
+        .open_with_wrapped_cek(&mut buf, &second, b"", &second_ceks[0], &keys[0])
+        .unwrap();
+    assert_eq!(&buf[..len], b"Second");
+    
+    // A ciphertext without a tag is truncated
+    let error = ChachaPolyIetf
+        .open_with_wrapped_cek(&mut buf, &second[..15], b"", &second_ceks[0], &keys[0])
+        .unwrap_err();
+    assert_eq!(error.to_string(), "TruncatedInput");
+}
\ No newline at end of file
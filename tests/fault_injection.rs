@@ -0,0 +1,49 @@
+#![cfg(feature = "test-fault-injection")]
+
+use crypto_api_chachapoly::{
+    ChachaPolyIetf, ChaCha20Ietf, fault_injection::arm_keystream_fault,
+    crypto_api::cipher::AeadCipher
+};
+
+
+/// Seals and opens `plaintext` and returns whether the round-trip succeeded
+fn roundtrip(plaintext: &[u8], fault_on_open: bool) -> bool {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+    let mut sealed = vec![0; plaintext.len() + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, plaintext, b"AD", &key, &nonce).unwrap();
+    
+    if fault_on_open { arm_keystream_fault() }
+    let mut buf = vec![0; plaintext.len()];
+    match ChachaPolyIetf.open_to(&mut buf, &sealed, b"AD", &key, &nonce) {
+        Ok(len) => &buf[..len] == plaintext,
+        Err(_) => false
+    }
+}
+
+
+#[test]
+fn test_negative_control() {
+    // The round-trip must succeed without and fail with a corrupted keystream
+    for &len in [0, 1, 64, 300].iter() {
+        let plaintext = vec![0x17; len];
+        assert!(roundtrip(&plaintext, false), "Length: {}", len);
+        assert!(!roundtrip(&plaintext, true), "Length: {}", len);
+        assert!(roundtrip(&plaintext, false), "Length: {}", len);
+    }
+}
+
+
+#[test]
+fn test_keystream_fault() {
+    // An armed fault must flip exactly one keystream bit of the next computation
+    let (key, nonce): (Vec<u8>, _) = ((0..32).collect(), [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0]);
+    let mut expected = vec![0; 64];
+    ChaCha20Ietf::xor(&key, &nonce, 1, &mut expected);
+    
+    arm_keystream_fault();
+    let mut buf = vec![0; 64];
+    ChaCha20Ietf::xor(&key, &nonce, 1, &mut buf);
+    assert_ne!(buf, expected);
+    assert_eq!(buf[0] ^ expected[0], 0x01);
+    assert_eq!(buf[1..], expected[1..]);
+}
\ No newline at end of file
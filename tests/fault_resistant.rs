@@ -0,0 +1,35 @@
+#![cfg(feature = "fault-resistant")]
+
+use crypto_api_chachapoly::{
+    ChachaPoly, ChachaPolyIetf, XChachaPoly, ChachaPolyError, crypto_api::cipher::AeadCipher
+};
+
+
+#[test]
+fn test_roundtrip() {
+    let ciphers: Vec<(Box<dyn AeadCipher>, usize)> = vec![
+        (Box::new(ChachaPoly), 8), (Box::new(ChachaPolyIetf), 12), (Box::new(XChachaPoly), 24)
+    ];
+    for (cipher, nonce_len) in ciphers {
+        let (key, nonce) = (vec![0x07; 32], vec![0x0e; nonce_len]);
+        let name = cipher.info().name;
+        for &len in [0, 1, 15, 16, 17, 64, 300].iter() {
+            // Seal and open the data
+            let plaintext = vec![0x17; len];
+            let mut sealed = vec![0; len + 16];
+            cipher.seal_to(&mut sealed, &plaintext, b"AD", &key, &nonce).unwrap();
+            let mut buf = vec![0; len];
+            assert_eq!(cipher.open_to(&mut buf, &sealed, b"AD", &key, &nonce).unwrap(), len);
+            assert_eq!(buf, plaintext, "Cipher: {}, length: {}", name, len);
+            
+            // Every modified tag byte must still be rejected
+            for pos in len..sealed.len() {
+                let mut tampered = sealed.clone();
+                tampered[pos] ^= 0x01;
+                let error = cipher.open_to(&mut buf, &tampered, b"AD", &key, &nonce).unwrap_err();
+                let error = error.downcast_ref::<ChachaPolyError>();
+                assert_eq!(error, Some(&ChachaPolyError::InvalidData), "Cipher: {}", name);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,87 @@
+#![cfg(feature = "force-soft")]
+
+use crypto_api_chachapoly::{ ChaCha20Ietf, ChachaPolyIetf, Poly1305, primitives::chacha20_permute };
+use json::JsonValue;
+use std::convert::TryInto;
+
+
+/// Gets the string field `name` of `vec`
+fn string(vec: &JsonValue, name: &str) -> String {
+    vec[name].as_str().unwrap().to_string()
+}
+/// Hex-decodes the string field `name` of `vec`
+fn bytes(vec: &JsonValue, name: &str) -> Vec<u8> {
+    hex::decode(vec[name].as_str().unwrap()).unwrap()
+}
+
+
+/// Computes the `n`th ChaCha20-IETF block with the portable scalar permutation
+fn soft_block(key: &[u8], nonce: &[u8], n: u32) -> Vec<u8> {
+    // Init the state
+    let mut init = [0; 16];
+    init[..4].copy_from_slice(&[0x61707865, 0x3320646e, 0x79622d32, 0x6b206574]);
+    let words = |bytes: &[u8]| -> Vec<u32> {
+        bytes.chunks(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect()
+    };
+    init[4..12].copy_from_slice(&words(key));
+    init[12] = n;
+    init[13..16].copy_from_slice(&words(nonce));
+    
+    // Mix the state and add the initial state
+    let mut state = init;
+    chacha20_permute(&mut state);
+    state.iter().zip(init.iter())
+        .flat_map(|(s, i)| s.wrapping_add(*i).to_le_bytes().to_vec())
+        .collect()
+}
+
+
+#[test]
+fn test_chacha20_ietf() {
+    let json = json::parse(include_str!("chacha20_ietf.json")).unwrap();
+    for vec in json["crypto"].members() {
+        let (name, key, nonce) = (string(vec, "name"), bytes(vec, "key"), bytes(vec, "nonce"));
+        let (plaintext, ciphertext) = (bytes(vec, "plaintext"), bytes(vec, "ciphertext"));
+        
+        // The ciphertext must match the known answer
+        let mut buf = vec![0; plaintext.len()];
+        ChaCha20Ietf::cipher().encrypt_to(&mut buf, &plaintext, &key, &nonce).unwrap();
+        assert_eq!(buf, ciphertext, "Test vector: \"{}\"", name);
+        
+        // The keystream must have been computed by the scalar permutation
+        let keystream: Vec<u8> = (0..buf.len().div_ceil(64) as u32)
+            .flat_map(|n| soft_block(&key, &nonce, n)).collect();
+        let expected: Vec<u8> = plaintext.iter().zip(keystream.iter())
+            .map(|(p, k)| p ^ k).collect();
+        assert_eq!(buf, expected, "Test vector: \"{}\"", name);
+    }
+}
+
+
+#[test]
+fn test_poly1305() {
+    let json = json::parse(include_str!("poly1305.json")).unwrap();
+    for vec in json["crypto"].members() {
+        let (name, key, data) = (string(vec, "name"), bytes(vec, "key"), bytes(vec, "data"));
+        
+        // The tag must match the known answer
+        let mut buf = vec![0; 16];
+        Poly1305::mac().auth(&mut buf, &data, &key).unwrap();
+        assert_eq!(buf, bytes(vec, "mac"), "Test vector: \"{}\"", name);
+    }
+}
+
+
+#[test]
+fn test_chachapoly_ietf() {
+    let json = json::parse(include_str!("chachapoly_ietf.json")).unwrap();
+    for vec in json["crypto"].members() {
+        let (name, key, nonce) = (string(vec, "name"), bytes(vec, "key"), bytes(vec, "nonce"));
+        let (ad, plaintext) = (bytes(vec, "ad"), bytes(vec, "plaintext"));
+        
+        // The sealed data must match the known answer
+        let mut buf = vec![0; plaintext.len() + 16];
+        ChachaPolyIetf::aead_cipher().seal_to(&mut buf, &plaintext, &ad, &key, &nonce).unwrap();
+        assert_eq!(buf, bytes(vec, "ciphertext"), "Test vector: \"{}\"", name);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,39 @@
+#![cfg(feature = "test-instrumentation")]
+
+use crypto_api_chachapoly::{
+    XChachaPoly, instrumentation::hchacha20_calls, crypto_api::cipher::AeadCipher
+};
+
+
+/// Calls `f` and returns the amount of HChaCha20 invocations it performed
+fn count_hchacha20(f: impl FnOnce()) -> usize {
+    let start = hchacha20_calls();
+    f();
+    hchacha20_calls() - start
+}
+
+
+#[test]
+fn test_xchachapoly_subkey() {
+    let (key, nonce) = ([0x17; 32], [0x2a; 24]);
+    let mut sealed = vec![0; 300 + 16];
+    let mut buf = vec![0; 300];
+    
+    // Sealing and opening must derive the subkey exactly once
+    let calls = count_hchacha20(|| {
+        XChachaPoly.seal_to(&mut sealed, &[0x07; 300], b"AD", &key, &nonce).unwrap();
+    });
+    assert_eq!(calls, 1);
+    let calls = count_hchacha20(|| {
+        XChachaPoly.open_to(&mut buf, &sealed, b"AD", &key, &nonce).unwrap();
+    });
+    assert_eq!(calls, 1);
+    assert_eq!(buf, vec![0x07; 300]);
+    
+    // ... even if the validation fails
+    sealed[0] ^= 0x01;
+    let calls = count_hchacha20(|| {
+        XChachaPoly.open_to(&mut buf, &sealed, b"AD", &key, &nonce).unwrap_err();
+    });
+    assert_eq!(calls, 1);
+}
\ No newline at end of file
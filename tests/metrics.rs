@@ -0,0 +1,95 @@
+#![cfg(feature = "metrics")]
+
+use crypto_api_chachapoly::{
+    ChachaPolyIetf, XChachaPoly, SEAL_COUNTER, OPEN_COUNTER, AUTH_FAILURE_COUNTER,
+    crypto_api::cipher::AeadCipher
+};
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit
+};
+use std::{
+    collections::HashMap,
+    sync::{ Arc, Mutex, atomic::{ AtomicU64, Ordering } }
+};
+
+
+/// A single counter value
+struct CounterValue(AtomicU64);
+impl CounterFn for CounterValue {
+    fn increment(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::SeqCst);
+    }
+    fn absolute(&self, value: u64) {
+        self.0.store(value, Ordering::SeqCst);
+    }
+}
+
+
+/// A recorder that keeps the counters in memory
+#[derive(Default)]
+struct TestRecorder {
+    counters: Mutex<HashMap<String, Arc<CounterValue>>>
+}
+impl TestRecorder {
+    /// Gets the value of the counter `name` with the label `algorithm`
+    pub fn get(&self, name: &str, algorithm: &str) -> u64 {
+        let counters = self.counters.lock().unwrap();
+        counters.get(&format!("{}/{}", name, algorithm))
+            .map(|c| c.0.load(Ordering::SeqCst))
+            .unwrap_or(0)
+    }
+}
+impl Recorder for TestRecorder {
+    fn describe_counter(&self, _key: KeyName, _unit: Option<Unit>, _desc: SharedString) {}
+    fn describe_gauge(&self, _key: KeyName, _unit: Option<Unit>, _desc: SharedString) {}
+    fn describe_histogram(&self, _key: KeyName, _unit: Option<Unit>, _desc: SharedString) {}
+    
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        let algorithm = key.labels().find(|l| l.key() == "algorithm").map(|l| l.value().to_string());
+        let id = format!("{}/{}", key.name(), algorithm.unwrap_or_default());
+        
+        let mut counters = self.counters.lock().unwrap();
+        let counter = counters.entry(id).or_insert_with(|| Arc::new(CounterValue(AtomicU64::new(0))));
+        Counter::from_arc(counter.clone())
+    }
+    fn register_gauge(&self, _key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+    fn register_histogram(&self, _key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        Histogram::noop()
+    }
+}
+
+
+#[test]
+fn test_counters() {
+    let recorder = TestRecorder::default();
+    metrics::with_local_recorder(&recorder, || {
+        let (key, nonce) = (vec![0x07; 32], vec![0x0e; 12]);
+        
+        // Seal and open a message
+        let mut buf = vec![0; 4 + 16];
+        ChachaPolyIetf.seal_to(&mut buf, b"Test", b"", &key, &nonce).unwrap();
+        ChachaPolyIetf.open(&mut buf.clone(), buf.len(), b"", &key, &nonce).unwrap();
+        
+        // A tampered message must bump the failure counter
+        buf[0] ^= 0x01;
+        ChachaPolyIetf.open(&mut buf.clone(), buf.len(), b"", &key, &nonce).unwrap_err();
+        ChachaPolyIetf.open(&mut buf.clone(), buf.len(), b"", &key, &nonce).unwrap_err();
+    });
+    assert_eq!(recorder.get(SEAL_COUNTER, "ChachaPolyIetf"), 1);
+    assert_eq!(recorder.get(OPEN_COUNTER, "ChachaPolyIetf"), 1);
+    assert_eq!(recorder.get(AUTH_FAILURE_COUNTER, "ChachaPolyIetf"), 2);
+}
+
+
+#[test]
+fn test_auth_failure_label() {
+    let recorder = TestRecorder::default();
+    metrics::with_local_recorder(&recorder, || {
+        let mut buf = vec![0; 16];
+        XChachaPoly.open(&mut buf, 16, b"", &[0; 32], &[0; 24]).unwrap_err();
+    });
+    assert_eq!(recorder.get(AUTH_FAILURE_COUNTER, "XChachaPoly"), 1);
+    assert_eq!(recorder.get(AUTH_FAILURE_COUNTER, "ChachaPolyIetf"), 0);
+}
\ No newline at end of file
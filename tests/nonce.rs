@@ -0,0 +1,86 @@
+use crypto_api_chachapoly::{
+    ChachaPolyIetf, XChachaPoly, ChachaPolyNonce, XChachaPolyNonce, Endian, ChachaPolyError, Misuse,
+    crypto_api::cipher::AeadCipher
+};
+use std::convert::TryFrom;
+
+
+#[test]
+fn test_from_slice() {
+    let buf: Vec<u8> = (0..64).collect();
+    
+    // Borrow the nonces from the middle of a larger buffer
+    let nonce = ChachaPolyNonce::from_slice(&buf[7..19]).unwrap();
+    assert_eq!(&nonce[..], &buf[7..19]);
+    assert_eq!(nonce.as_ptr(), buf[7..].as_ptr());
+    let nonce = XChachaPolyNonce::from_slice(&buf[7..31]).unwrap();
+    assert_eq!(&nonce[..], &buf[7..31]);
+    assert_eq!(nonce.as_ptr(), buf[7..].as_ptr());
+    
+    // The view can be used as nonce directly
+    let (key, mut sealed) = (vec![0; 32], vec![0; 16]);
+    let nonce = ChachaPolyNonce::from_slice(&buf[..12]).unwrap();
+    ChachaPolyIetf.seal_to(&mut sealed, b"", b"", &key, nonce).unwrap();
+    
+    // Invalid lengths
+    for &len in [0, 11, 13, 23, 25].iter() {
+        assert!(XChachaPolyNonce::from_slice(&buf[..len]).is_none(), "Length: {}", len);
+        assert!(ChachaPolyNonce::from_slice(&buf[..len]).is_none(), "Length: {}", len);
+    }
+}
+
+
+#[test]
+fn test_try_from() {
+    let buf: Vec<u8> = (0..64).collect();
+    
+    // Copy the nonces
+    let nonce = ChachaPolyNonce::try_from(&buf[..12]).unwrap();
+    assert_eq!(nonce.as_ref(), &buf[..12]);
+    let nonce = XChachaPolyNonce::try_from(&buf[..24]).unwrap();
+    assert_eq!(nonce.as_ref(), &buf[..24]);
+    
+    // Invalid lengths
+    assert_eq!(
+        ChachaPolyNonce::try_from(&buf[..11]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 12, got: 11 }))
+    );
+    assert_eq!(
+        XChachaPolyNonce::try_from(&buf[..25]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 24, got: 25 }))
+    );
+}
+
+#[test]
+fn test_nonce_from_counter() {
+    let counter = 0x0102_0304_0506_0708;
+    
+    // ChachaPoly-IETF nonces
+    let nonce = ChachaPolyIetf::nonce_from_counter(&[0xa0, 0xa1, 0xa2, 0xa3], counter, Endian::Big);
+    assert_eq!(nonce.unwrap(), [0xa0, 0xa1, 0xa2, 0xa3, 1, 2, 3, 4, 5, 6, 7, 8]);
+    let nonce = ChachaPolyIetf::nonce_from_counter(&[0; 4], counter, Endian::Little);
+    assert_eq!(nonce.unwrap(), [0, 0, 0, 0, 8, 7, 6, 5, 4, 3, 2, 1]);
+    
+    // XChachaPoly nonces
+    let salt = [0x5a; 16];
+    let nonce = XChachaPoly::nonce_from_counter(&salt, counter, Endian::Big).unwrap();
+    assert_eq!(&nonce[..16], &salt);
+    assert_eq!(&nonce[16..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+    let nonce = XChachaPoly::nonce_from_counter(&salt, counter, Endian::Little).unwrap();
+    assert_eq!(&nonce[..16], &salt);
+    assert_eq!(&nonce[16..], &[8, 7, 6, 5, 4, 3, 2, 1]);
+    
+    // Invalid salt lengths
+    for &len in [0, 3, 5, 12].iter() {
+        assert_eq!(
+            ChachaPolyIetf::nonce_from_counter(&vec![0; len], counter, Endian::Big),
+            Err(ChachaPolyError::ApiMisuse(Misuse::SaltLen{ expected: 4, got: len }))
+        );
+    }
+    for &len in [0, 4, 15, 17, 24].iter() {
+        assert_eq!(
+            XChachaPoly::nonce_from_counter(&vec![0; len], counter, Endian::Little),
+            Err(ChachaPolyError::ApiMisuse(Misuse::SaltLen{ expected: 16, got: len }))
+        );
+    }
+}
\ No newline at end of file
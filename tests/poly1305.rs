@@ -1,7 +1,7 @@
 mod shared;
 
 use shared::{ JsonValueExt, ResultExt };
-use crypto_api_chachapoly::Poly1305;
+use crypto_api_chachapoly::{ Poly1305, ChachaPolyError, Misuse };
 use json::JsonValue;
 
 
@@ -40,6 +40,19 @@ impl CryptoTestVector {
         Poly1305::mac().auth(&mut buf, &self.data, &self.key).unwrap();
         assert_eq!(buf, self.mac, "Test vector: \"{}\"", self.name);
         
+        // Compute mac with explicit RFC padding
+        let mut buf = vec![0; self.mac.len()];
+        Poly1305::auth_raw(&mut buf, &self.data, &self.key, true).unwrap();
+        assert_eq!(buf, self.mac, "Test vector: \"{}\"", self.name);
+        
+        // Compute mac with a split key
+        let (mut r, mut s) = ([0; 16], [0; 16]);
+        r.copy_from_slice(&self.key[..16]);
+        s.copy_from_slice(&self.key[16..]);
+        let mut buf = vec![0; self.mac.len()];
+        Poly1305::auth_with_rs(&mut buf, &self.data, &r, &s).unwrap();
+        assert_eq!(buf, self.mac, "Test vector: \"{}\"", self.name);
+        
         self
     }
 }
@@ -49,6 +62,90 @@ fn test_crypto() {
         vec.test_mac();
     }
 }
+#[test]
+fn test_auth_with_rs_clamping() {
+    // The key from RFC 8439 section 2.5.2 and its clamped `r`
+    let (mut r, mut clamped_r, mut s) = ([0; 16], [0; 16], [0; 16]);
+    r.copy_from_slice(&hex::decode("85d6be7857556d337f4452fe42d506a8").unwrap());
+    clamped_r.copy_from_slice(&hex::decode("85d6be0854556d037c44520e40d50608").unwrap());
+    s.copy_from_slice(&hex::decode("0103808afb0db2fd4abff6af4149f51b").unwrap());
+    
+    // Both must produce the tag from RFC 8439 section 2.5.2
+    let data = b"Cryptographic Forum Research Group";
+    let expected = hex::decode("a8061dc1305136c6c22b8baf0c0127a9").unwrap();
+    let (mut a, mut b) = (vec![0; 16], vec![0; 16]);
+    Poly1305::auth_with_rs(&mut a, data, &r, &s).unwrap();
+    Poly1305::auth_with_rs(&mut b, data, &clamped_r, &s).unwrap();
+    assert_eq!(a, expected);
+    assert_eq!(b, expected);
+}
+#[test]
+fn test_auth_raw_unpadded() {
+    let (key, data) = ([0x42; 32], [0x17; 33]);
+    
+    // An incomplete final block must change the tag if it is not padded
+    let (mut padded, mut unpadded) = ([0; 16], [0; 16]);
+    Poly1305::auth_raw(&mut padded, &data, &key, true).unwrap();
+    Poly1305::auth_raw(&mut unpadded, &data, &key, false).unwrap();
+    assert_ne!(padded, unpadded);
+    
+    // A complete final block is never padded
+    Poly1305::auth_raw(&mut padded, &data[..32], &key, true).unwrap();
+    Poly1305::auth_raw(&mut unpadded, &data[..32], &key, false).unwrap();
+    assert_eq!(padded, unpadded);
+}
+#[test]
+fn test_chachapoly_mac() {
+    // The AEAD example from RFC 8439 section 2.8.2
+    let key = hex::decode("7bac2b252db447af09b67a55a4e955840ae1d6731075d9eb2a9375783ed553ff")
+        .unwrap();
+    let ad = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+    let ciphertext = hex::decode(concat!(
+        "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69",
+        "da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad67594",
+        "5585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116"
+    )).unwrap();
+    
+    let mut poly_key = [0; 32];
+    poly_key.copy_from_slice(&key);
+    let tag = Poly1305::chachapoly_mac(&ad, &ciphertext, &poly_key);
+    assert_eq!(tag.to_vec(), hex::decode("1ae10b594f09e26a7e902ecbd0600691").unwrap());
+}
+#[test]
+fn test_verify_into() {
+    // The example from RFC 8439 section 2.5.2
+    let key = hex::decode("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b")
+        .unwrap();
+    let tag = hex::decode("a8061dc1305136c6c22b8baf0c0127a9").unwrap();
+    let data = b"Cryptographic Forum Research Group";
+    
+    // A matching tag
+    let mut buf = vec![0; 16];
+    assert_eq!(Poly1305.verify_into(&mut buf, data, &key, &tag), Ok(()));
+    assert_eq!(buf, tag);
+    
+    // A mismatching tag
+    for pos in 0..tag.len() {
+        let mut tampered = tag.clone();
+        tampered[pos] ^= 0x01;
+        assert_eq!(
+            Poly1305.verify_into(&mut buf, data, &key, &tampered),
+            Err(ChachaPolyError::InvalidData), "Position: {}", pos
+        );
+    }
+    
+    // An expected tag with an invalid length
+    for &len in [0, 15, 17, 32].iter() {
+        assert_eq!(
+            Poly1305.verify_into(&mut buf, data, &key, &vec![0; len]),
+            Err(ChachaPolyError::ApiMisuse(Misuse::BufferLen)), "Length: {}", len
+        );
+    }
+    assert_eq!(
+        Poly1305.verify_into(&mut buf[..15], data, &key, &tag),
+        Err(ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{ needed: 16, got: 15 }))
+    );
+}
 
 
 /// An API test vector
@@ -0,0 +1,89 @@
+use crypto_api_chachapoly::{
+    ChachaPolyIetf, XChachaPoly, ChachaPolyError, crypto_api::cipher::AeadCipher
+};
+use proptest::{
+    prelude::*,
+    collection::vec,
+    sample::Index,
+    test_runner::TestCaseError
+};
+
+
+/// The exclusive upper bound for the generated plaintext and AD lengths
+const LIMIT: usize = 300;
+
+
+/// Asserts that sealing has the expected length and that seal-then-open is the identity
+fn roundtrip(cipher: &dyn AeadCipher, key: &[u8], nonce: &[u8], ad: &[u8], plaintext: &[u8])
+    -> Result<(), TestCaseError>
+{
+    // Seal the plaintext
+    let mut sealed = vec![0; plaintext.len() + 16];
+    let len = cipher.seal_to(&mut sealed, plaintext, ad, key, nonce).unwrap();
+    prop_assert_eq!(len, plaintext.len() + 16);
+    
+    // Open the ciphertext
+    let mut opened = vec![0; plaintext.len()];
+    let len = cipher.open_to(&mut opened, &sealed, ad, key, nonce).unwrap();
+    prop_assert_eq!(len, plaintext.len());
+    prop_assert_eq!(opened, plaintext);
+    Ok(())
+}
+
+/// Asserts that flipping the bit `bit` at `index` in the ciphertext/tag or the AD is detected
+#[allow(clippy::too_many_arguments)]
+fn tamper(cipher: &dyn AeadCipher, key: &[u8], nonce: &[u8], ad: &[u8], plaintext: &[u8],
+    index: Index, bit: u8, tamper_ad: bool) -> Result<(), TestCaseError>
+{
+    // Seal the plaintext
+    let mut sealed = vec![0; plaintext.len() + 16];
+    cipher.seal_to(&mut sealed, plaintext, ad, key, nonce).unwrap();
+    
+    // Flip a bit in the AD (if any) or in the ciphertext/tag
+    let mut ad = ad.to_vec();
+    let target = match tamper_ad && !ad.is_empty() {
+        true => &mut ad,
+        false => &mut sealed
+    };
+    let pos = index.index(target.len());
+    target[pos] ^= 1 << bit;
+    
+    // Open the tampered data
+    let mut opened = vec![0; plaintext.len()];
+    let error = cipher.open_to(&mut opened, &sealed, &ad, key, nonce).unwrap_err();
+    prop_assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&ChachaPolyError::InvalidData));
+    Ok(())
+}
+
+
+proptest! {
+    #[test]
+    fn test_chachapoly_ietf_roundtrip(key in vec(any::<u8>(), 32), nonce in vec(any::<u8>(), 12),
+        ad in vec(any::<u8>(), 0..LIMIT), plaintext in vec(any::<u8>(), 0..LIMIT))
+    {
+        roundtrip(&ChachaPolyIetf, &key, &nonce, &ad, &plaintext)?;
+    }
+    
+    #[test]
+    fn test_chachapoly_ietf_tamper(key in vec(any::<u8>(), 32), nonce in vec(any::<u8>(), 12),
+        ad in vec(any::<u8>(), 0..LIMIT), plaintext in vec(any::<u8>(), 0..LIMIT),
+        index in any::<Index>(), bit in 0..8u8, tamper_ad in any::<bool>())
+    {
+        tamper(&ChachaPolyIetf, &key, &nonce, &ad, &plaintext, index, bit, tamper_ad)?;
+    }
+    
+    #[test]
+    fn test_xchachapoly_roundtrip(key in vec(any::<u8>(), 32), nonce in vec(any::<u8>(), 24),
+        ad in vec(any::<u8>(), 0..LIMIT), plaintext in vec(any::<u8>(), 0..LIMIT))
+    {
+        roundtrip(&XChachaPoly, &key, &nonce, &ad, &plaintext)?;
+    }
+    
+    #[test]
+    fn test_xchachapoly_tamper(key in vec(any::<u8>(), 32), nonce in vec(any::<u8>(), 24),
+        ad in vec(any::<u8>(), 0..LIMIT), plaintext in vec(any::<u8>(), 0..LIMIT),
+        index in any::<Index>(), bit in 0..8u8, tamper_ad in any::<bool>())
+    {
+        tamper(&XChachaPoly, &key, &nonce, &ad, &plaintext, index, bit, tamper_ad)?;
+    }
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+use crypto_api_chachapoly::{ SecretKey, crypto_api::rng::SecureRng };
+use std::error::Error;
+
+
+/// A deterministic (and thus insecure!) RNG that produces a different byte sequence on each call
+struct CounterRng(u8);
+impl SecureRng for CounterRng {
+    fn random(&mut self, buf: &mut[u8]) -> Result<(), Box<dyn Error + 'static>> {
+        buf.iter_mut().for_each(|b| { *b = self.0; self.0 = self.0.wrapping_add(1) });
+        Ok(())
+    }
+}
+
+
+#[test]
+fn test_generate() {
+    let mut rng = CounterRng(0);
+    let (a, b) = (SecretKey::generate(&mut rng).unwrap(), SecretKey::generate(&mut rng).unwrap());
+    assert_eq!(a.len(), 32);
+    assert_eq!(b.as_ref().len(), 32);
+    assert_ne!(&a[..], &b[..]);
+}
@@ -0,0 +1,102 @@
+use crypto_api_chachapoly::{ ChachaPolyWriter, ChachaPolyReader, ChachaPolyError, Misuse };
+use std::io::{ ErrorKind, Read, Write };
+
+
+/// The plaintext chunk size
+const CHUNK: usize = 64 * 1024;
+/// The sealed chunk size
+const SEALED_CHUNK: usize = CHUNK + 16;
+
+/// The key
+const KEY: [u8; 32] = [0x07; 32];
+/// The nonce prefix
+const PREFIX: [u8; 7] = [0x0e; 7];
+
+
+/// Creates a `len`-sized plaintext
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 + 3) as u8).collect()
+}
+
+/// Encrypts `plaintext` through a `ChachaPolyWriter` in `piece`-sized writes
+fn seal(plaintext: &[u8], piece: usize) -> Vec<u8> {
+    let mut writer = ChachaPolyWriter::new(Vec::new(), &KEY, &PREFIX).unwrap();
+    plaintext.chunks(piece).for_each(|p| writer.write_all(p).unwrap());
+    writer.finish().unwrap()
+}
+
+/// Decrypts `sealed` through a `ChachaPolyReader`
+fn open(sealed: &[u8]) -> Result<Vec<u8>, ErrorKind> {
+    let mut reader = ChachaPolyReader::new(sealed, &KEY, &PREFIX).unwrap();
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).map_err(|e| e.kind())?;
+    Ok(plaintext)
+}
+
+
+#[test]
+fn test_roundtrip() {
+    for &len in [0, 1, CHUNK - 1, CHUNK, CHUNK + 1, 2 * CHUNK, 3 * CHUNK + 17].iter() {
+        for &piece in [1000, CHUNK, 3 * CHUNK].iter() {
+            let plaintext = plaintext(len);
+            let sealed = seal(&plaintext, piece);
+            
+            // Every chunk but the last is full; the last chunk may be empty
+            let chunks = match len { 0 => 1, len => len.div_ceil(CHUNK) };
+            assert_eq!(sealed.len(), len + chunks * 16, "Length: {}", len);
+            assert_eq!(open(&sealed).unwrap(), plaintext, "Length: {}", len);
+        }
+    }
+}
+
+
+#[test]
+fn test_truncation() {
+    let sealed = seal(&plaintext(3 * CHUNK + 17), CHUNK);
+    
+    // A stream without the last chunk or without any chunk must be rejected
+    assert_eq!(open(&sealed[..3 * SEALED_CHUNK]), Err(ErrorKind::InvalidData));
+    assert_eq!(open(&sealed[..SEALED_CHUNK]), Err(ErrorKind::InvalidData));
+    assert_eq!(open(&[]), Err(ErrorKind::InvalidData));
+    
+    // A truncated chunk must be rejected
+    assert_eq!(open(&sealed[..sealed.len() - 1]), Err(ErrorKind::InvalidData));
+    assert_eq!(open(&sealed[..SEALED_CHUNK + 15]), Err(ErrorKind::InvalidData));
+}
+
+
+#[test]
+fn test_tampering() {
+    let sealed = seal(&plaintext(2 * CHUNK + 17), CHUNK);
+    
+    // Flip a bit in each chunk
+    for &pos in [0, SEALED_CHUNK + 5, sealed.len() - 1].iter() {
+        let mut tampered = sealed.clone();
+        tampered[pos] ^= 0x01;
+        assert_eq!(open(&tampered), Err(ErrorKind::InvalidData), "Position: {}", pos);
+    }
+    
+    // Reorder the first two chunks
+    let mut reordered = sealed[SEALED_CHUNK..2 * SEALED_CHUNK].to_vec();
+    reordered.extend_from_slice(&sealed[..SEALED_CHUNK]);
+    reordered.extend_from_slice(&sealed[2 * SEALED_CHUNK..]);
+    assert_eq!(open(&reordered), Err(ErrorKind::InvalidData));
+    
+    // Append another chunk
+    let mut extended = sealed.clone();
+    extended.extend_from_slice(&seal(b"", CHUNK));
+    assert_eq!(open(&extended), Err(ErrorKind::InvalidData));
+}
+
+
+#[test]
+fn test_api() {
+    assert_eq!(
+        ChachaPolyWriter::new(Vec::new(), &KEY[..31], &PREFIX).err(),
+        Some(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    assert_eq!(
+        ChachaPolyReader::new(&[][..], &KEY, &[0; 12]).err(),
+        Some(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 7, got: 12 }))
+    );
+}
\ No newline at end of file
@@ -0,0 +1,49 @@
+#![cfg(feature = "subtle")]
+
+use crypto_api_chachapoly::{
+    ChachaPoly, ChachaPolyIetf, XChachaPoly, ChachaPolyError, Tag, crypto_api::cipher::AeadCipher
+};
+
+
+#[test]
+fn test_open() {
+    let ciphers: Vec<(Box<dyn AeadCipher>, usize)> = vec![
+        (Box::new(ChachaPoly), 8), (Box::new(ChachaPolyIetf), 12), (Box::new(XChachaPoly), 24)
+    ];
+    for (cipher, nonce_len) in ciphers {
+        let (key, nonce) = (vec![0x2a; 32], vec![0x17; nonce_len]);
+        let name = cipher.info().name;
+        for &len in [0, 1, 16, 65].iter() {
+            // Seal and open the data
+            let plaintext = vec![0x4e; len];
+            let mut sealed = vec![0; len + 16];
+            cipher.seal_to(&mut sealed, &plaintext, b"AD", &key, &nonce).unwrap();
+            let mut buf = vec![0; len];
+            assert_eq!(cipher.open_to(&mut buf, &sealed, b"AD", &key, &nonce).unwrap(), len);
+            assert_eq!(buf, plaintext, "Cipher: {}, length: {}", name, len);
+            
+            // A modified ciphertext or tag must be rejected
+            for pos in 0..sealed.len() {
+                let mut tampered = sealed.clone();
+                tampered[pos] ^= 0x80;
+                let error = cipher.open_to(&mut buf, &tampered, b"AD", &key, &nonce).unwrap_err();
+                let error = error.downcast_ref::<ChachaPolyError>();
+                assert_eq!(error, Some(&ChachaPolyError::InvalidData), "Cipher: {}", name);
+            }
+        }
+    }
+}
+
+
+#[test]
+fn test_tag_eq() {
+    let tag = Tag([0x2a; 16]);
+    assert_eq!(tag, Tag([0x2a; 16]));
+    
+    // Any differing bit must make the tags unequal
+    for pos in 0..128 {
+        let mut other = tag;
+        other.0[pos / 8] ^= 1 << (pos % 8);
+        assert_ne!(tag, other, "Bit: {}", pos);
+    }
+}
\ No newline at end of file
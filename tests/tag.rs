@@ -0,0 +1,22 @@
+use crypto_api_chachapoly::Tag;
+
+
+#[test]
+fn test_eq() {
+    let a = [0x5a; 16];
+    let mut values = vec![a, [0x00; 16], [0xff; 16]];
+    
+    // Flip every single bit of `a`
+    for i in 0..128 {
+        let mut b = a;
+        b[i / 8] ^= 1 << (i % 8);
+        values.push(b);
+    }
+    
+    // The constant time comparison must agree with the byte comparison
+    for x in values.iter() {
+        for y in values.iter() {
+            assert_eq!(Tag(*x) == Tag(*y), x == y, "Tags: {:?} and {:?}", x, y);
+        }
+    }
+}
\ No newline at end of file
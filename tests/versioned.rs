@@ -0,0 +1,92 @@
+use crypto_api_chachapoly::{
+    VersionedAead, AeadAlgorithm, ChachaPolyIetf, ChachaPolyError, Misuse,
+    crypto_api::cipher::AeadCipher
+};
+use std::error::Error;
+
+
+/// Extracts the `ChachaPolyError` from `error`
+fn chachapoly_error(error: Box<dyn Error + 'static>) -> ChachaPolyError {
+    *error.downcast::<ChachaPolyError>().unwrap()
+}
+
+
+#[test]
+fn test_seal_v1() {
+    let (key, nonce) = ([0x17; 32], [0x2a; 12]);
+    let aead = VersionedAead::new(AeadAlgorithm::ChachaPolyIetf);
+    let message = aead.seal(b"Versioned", b"AD", &key, &nonce).unwrap();
+    
+    // The message must be `0x01 || nonce || ChachaPoly-IETF ciphertext`
+    let mut sealed = vec![0; 9 + 16];
+    ChachaPolyIetf.seal_to(&mut sealed, b"Versioned", b"AD", &key, &nonce).unwrap();
+    assert_eq!(message[0], 0x01);
+    assert_eq!(&message[1..13], &nonce);
+    assert_eq!(&message[13..], sealed.as_slice());
+}
+
+
+#[test]
+fn test_dispatch() {
+    let key = [0x17; 32];
+    let v1 = VersionedAead::new(AeadAlgorithm::ChachaPolyIetf);
+    let v2 = VersionedAead::new(AeadAlgorithm::XChachaPoly);
+    let message_v1 = v1.seal(b"Old", b"AD", &key, &[0x01; 12]).unwrap();
+    let message_v2 = v2.seal(b"New", b"AD", &key, &[0x02; 24]).unwrap();
+    assert_eq!(message_v2[0], 0x02);
+    
+    // Both instances must open both versions
+    for aead in [v1, v2].iter() {
+        assert_eq!(aead.open(&message_v1, b"AD", &key).unwrap(), b"Old");
+        assert_eq!(aead.open(&message_v2, b"AD", &key).unwrap(), b"New");
+    }
+    
+    // A modified version byte must not be accepted
+    let mut tampered = message_v2.clone();
+    tampered[0] = 0x01;
+    assert!(v2.open(&tampered, b"AD", &key).is_err());
+}
+
+
+#[test]
+fn test_errors() {
+    let (key, aead) = ([0x17; 32], VersionedAead::new(AeadAlgorithm::ChachaPolyIetf));
+    let message = aead.seal(b"", b"", &key, &[0; 12]).unwrap();
+    
+    // An unknown version
+    for &id in [0x00, 0x03, 0xff].iter() {
+        let mut unknown = message.clone();
+        unknown[0] = id;
+        assert_eq!(
+            chachapoly_error(aead.open(&unknown, b"", &key).unwrap_err()),
+            ChachaPolyError::ApiMisuse(Misuse::UnknownAlgorithm(id))
+        );
+    }
+    assert_eq!(AeadAlgorithm::from_id(0x02), Ok(AeadAlgorithm::XChachaPoly));
+    
+    // A truncated message
+    for &len in [0, 1, 12, 13, 28].iter() {
+        assert_eq!(
+            chachapoly_error(aead.open(&message[..len], b"", &key).unwrap_err()),
+            ChachaPolyError::TruncatedInput, "Length: {}", len
+        );
+    }
+    let x_aead = VersionedAead::new(AeadAlgorithm::XChachaPoly);
+    let x_message = x_aead.seal(b"", b"", &key, &[0; 24]).unwrap();
+    for &len in [24, 25, 40].iter() {
+        assert_eq!(
+            chachapoly_error(aead.open(&x_message[..len], b"", &key).unwrap_err()),
+            ChachaPolyError::TruncatedInput, "Length: {}", len
+        );
+    }
+    
+    // An invalid nonce length
+    assert_eq!(
+        chachapoly_error(aead.seal(b"", b"", &key, &[0; 24]).unwrap_err()),
+        ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 12, got: 24 })
+    );
+    assert_eq!(
+        chachapoly_error(x_aead.seal(b"", b"", &key, &[0; 12]).unwrap_err()),
+        ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 24, got: 12 })
+    );
+}
\ No newline at end of file
@@ -1,7 +1,7 @@
 mod shared;
 
 use shared::{ JsonValueExt, ResultExt };
-use crypto_api_chachapoly::XChaCha20;
+use crypto_api_chachapoly::{ XChaCha20, XChaCha20Session, ChachaPolyError, Misuse };
 use json::JsonValue;
 
 
@@ -154,4 +154,62 @@ fn test_api() {
     for vec in ApiTestVector::load() {
         vec.test_encryption().test_decryption();
     }
+}
+
+#[test]
+fn test_session() {
+    let key: Vec<u8> = (0..32).collect();
+    let (mut prefix, data): ([u8; 16], Vec<u8>) = ([0; 16], (0..300).map(|i| i as u8).collect());
+    prefix.iter_mut().enumerate().for_each(|(i, b)| *b = 0xa0 + i as u8);
+    let session = XChaCha20Session::new(&key, &prefix).unwrap();
+    
+    // The session must produce the same output as the stateless implementation for all suffixes
+    for suffix in 0..8u64 {
+        let suffix = (suffix * 0x0101_0101_0101_0101).to_le_bytes();
+        for &n in [0, 1, 7, u32::MAX as u64 + 1].iter() {
+            let mut nonce = prefix.to_vec();
+            nonce.extend_from_slice(&suffix);
+            let mut expected = data.clone();
+            XChaCha20::xor(&key, &nonce, n, &mut expected);
+            
+            let mut buf = data.clone();
+            session.xor(&suffix, n, &mut buf);
+            assert_eq!(buf, expected, "Suffix: {:?}, block: {}", suffix, n);
+        }
+    }
+    
+    // Invalid key
+    assert_eq!(
+        XChaCha20Session::new(&key[..31], &prefix).err(),
+        Some(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+}
+
+#[test]
+fn test_in_place() {
+    let (key, nonce) = (vec![0x07; 32], vec![0x0e; 24]);
+    for &len in [0, 1, 63, 64, 65, 300].iter() {
+        // Encrypt the full buffer via the `Cipher` interface
+        let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let mut expected = plaintext.clone();
+        XChaCha20::cipher().encrypt(&mut expected, len, &key, &nonce).unwrap();
+        
+        // Encrypt and decrypt in place
+        let mut buf = plaintext.clone();
+        XChaCha20::encrypt_in_place(&mut buf, &key, &nonce).unwrap();
+        assert_eq!(buf, expected, "Length: {}", len);
+        XChaCha20::decrypt_in_place(&mut buf, &key, &nonce).unwrap();
+        assert_eq!(buf, plaintext, "Length: {}", len);
+    }
+    
+    // Invalid parameters
+    let mut buf = vec![0; 16];
+    assert_eq!(
+        XChaCha20::encrypt_in_place(&mut buf, &key[..31], &nonce),
+        Err(ChachaPolyError::ApiMisuse(Misuse::KeyLen{ expected: 32, got: 31 }))
+    );
+    assert_eq!(
+        XChaCha20::decrypt_in_place(&mut buf, &key, &nonce[1..]),
+        Err(ChachaPolyError::ApiMisuse(Misuse::NonceLen{ expected: 24, got: 23 }))
+    );
 }
\ No newline at end of file
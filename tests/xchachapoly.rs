@@ -1,7 +1,9 @@
 mod shared;
 
 use shared::{ JsonValueExt, ResultExt };
-use crypto_api_chachapoly::XChachaPoly;
+use crypto_api_chachapoly::{
+    XChachaPoly, ChachaPolyError, Misuse, Tag, crypto_api::cipher::AeadCipher
+};
 use json::JsonValue;
 
 
@@ -54,6 +56,15 @@ impl CryptoTestVector {
             .unwrap();
         assert_eq!(buf, self.ciphertext, "Test vector: \"{}\"", self.name);
         
+        // Encrypt to buffer with a detached tag
+        let (ciphertext, tag) = self.split_tag();
+        let mut buf = vec![0; self.plaintext.len()];
+        let detached = XChachaPoly
+            .seal_detached_to(&mut buf, &self.plaintext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(buf, ciphertext, "Test vector: \"{}\"", self.name);
+        assert_eq!(detached, Tag(tag), "Test vector: \"{}\"", self.name);
+        
         self
     }
     
@@ -73,13 +84,64 @@ impl CryptoTestVector {
             .unwrap();
         assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
         
+        // Decrypt with ciphertext and tag in separate buffers
+        let (ciphertext, tag) = self.split_tag();
+        let mut buf = vec![0; self.plaintext.len()];
+        let len = XChachaPoly
+            .open_detached_to(&mut buf, &ciphertext, &tag, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(len, self.plaintext.len(), "Test vector: \"{}\"", self.name);
+        assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
+        
+        self
+    }
+    
+    /// Tests the tag-prefixed layout
+    pub fn test_tag_prefixed(&self) -> &Self {
+        // The prefixed layout must be the suffixed layout with the tag moved to the front
+        let (ciphertext, tag) = self.split_tag();
+        let prefixed = [&tag[..], &ciphertext].concat();
+        
+        // Seal with a prefixed tag
+        let mut buf = vec![0; self.ciphertext.len()];
+        let len = XChachaPoly
+            .seal_tag_prefixed(&mut buf, &self.plaintext, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(len, self.ciphertext.len(), "Test vector: \"{}\"", self.name);
+        assert_eq!(buf, prefixed, "Test vector: \"{}\"", self.name);
+        
+        // Open with a prefixed tag
+        let mut buf = vec![0; self.plaintext.len()];
+        let len = XChachaPoly
+            .open_tag_prefixed(&mut buf, &prefixed, &self.ad, &self.key, &self.nonce)
+            .unwrap();
+        assert_eq!(len, self.plaintext.len(), "Test vector: \"{}\"", self.name);
+        assert_eq!(buf, self.plaintext, "Test vector: \"{}\"", self.name);
+        
+        // A suffixed ciphertext must not be accepted as prefixed one (unless it's only a tag)
+        if !self.plaintext.is_empty() {
+            let mut buf = vec![0; self.plaintext.len()];
+            let error = XChachaPoly
+                .open_tag_prefixed(&mut buf, &self.ciphertext, &self.ad, &self.key, &self.nonce)
+                .unwrap_err();
+            assert_eq!(error, ChachaPolyError::InvalidData, "Test vector: \"{}\"", self.name);
+        }
+        
         self
     }
+    
+    /// Copies the ciphertext and the tag into two distinct allocations
+    fn split_tag(&self) -> (Vec<u8>, [u8; 16]) {
+        let (ciphertext, tag_slice) = self.ciphertext.split_at(self.ciphertext.len() - 16);
+        let mut tag = [0; 16];
+        tag.copy_from_slice(tag_slice);
+        (ciphertext.to_vec(), tag)
+    }
 }
 #[test]
 fn test_crypto() {
     for vec in CryptoTestVector::load() {
-        vec.test_encryption().test_decryption();
+        vec.test_encryption().test_decryption().test_tag_prefixed();
     }
 }
 
@@ -126,6 +188,19 @@ impl ErrorTestVector {
             .error_or(format!("Test vector: \"{}\"", self.name));
         assert_eq!(error.to_string(), "InvalidData", "Test vector: \"{}\"", self.name);
         
+        // Decrypt with ciphertext and tag in separate buffers (if there is a complete tag)
+        if self.ciphertext.len() >= 16 {
+            let (ciphertext, tag_slice) = self.ciphertext.split_at(self.ciphertext.len() - 16);
+            let (ciphertext, mut tag) = (ciphertext.to_vec(), [0; 16]);
+            tag.copy_from_slice(tag_slice);
+            
+            let mut buf = vec![0; ciphertext.len()];
+            let error = XChachaPoly
+                .open_detached_to(&mut buf, &ciphertext, &tag, &self.ad, &self.key, &self.nonce)
+                .error_or(format!("Test vector: \"{}\"", self.name));
+            assert_eq!(error.to_string(), "InvalidData", "Test vector: \"{}\"", self.name);
+        }
+        
         self
     }
 }
@@ -148,7 +223,9 @@ pub struct ApiTestVector {
     enc_buf_len: usize,
     dec_input_len: usize,
     dec_buf_len: usize,
-    error: String
+    enc_error: String,
+    dec_error: String,
+    dec_in_place_error: String
 }
 impl ApiTestVector {
     /// Loads the test vectors
@@ -181,13 +258,13 @@ impl ApiTestVector {
         let error = XChachaPoly::aead_cipher()
             .seal(&mut buf, input.len(), &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), self.enc_error, "Test vector: \"{}\"", self.name);
         
         // Encrypt in buffer
         let error = XChachaPoly::aead_cipher()
             .seal_to(&mut buf, &input, &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), self.enc_error, "Test vector: \"{}\"", self.name);
         
         self
     }
@@ -205,13 +282,14 @@ impl ApiTestVector {
         let error = XChachaPoly::aead_cipher()
             .open(&mut buf, input.len(), &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        let expected = &self.dec_in_place_error;
+        assert_eq!(&error.to_string(), expected, "Test vector: \"{}\"", self.name);
         
         // Decrypt in buffer
         let error = XChachaPoly::aead_cipher()
             .open_to(&mut buf, &input, &ad, &key, &nonce)
             .error_or(format!("Test vector: \"{}\"", self.name));
-        assert_eq!(error.to_string(), self.error, "Test vector: \"{}\"", self.name);
+        assert_eq!(error.to_string(), self.dec_error, "Test vector: \"{}\"", self.name);
         
         self
     }
@@ -226,7 +304,11 @@ impl ApiTestVector {
         self.enc_buf_len = j["enc_buf_len"].optional_usize(self.enc_buf_len);
         self.dec_input_len = j["dec_input_len"].optional_usize(self.dec_input_len);
         self.dec_buf_len = j["dec_buf_len"].optional_usize(self.dec_buf_len);
-        self.error = j["error"].optional_string(&self.error);
+        self.enc_error = j["error"].optional_string(&self.enc_error);
+        self.enc_error = j["enc_error"].optional_string(&self.enc_error);
+        self.dec_error = j["error"].optional_string(&self.dec_error);
+        self.dec_error = j["dec_error"].optional_string(&self.dec_error);
+        self.dec_in_place_error = j["dec_in_place_error"].optional_string(&self.dec_error);
     }
 }
 #[test]
@@ -234,4 +316,131 @@ fn test_api() {
     for vec in ApiTestVector::load() {
         vec.test_encryption().test_decryption();
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_api_huge_len() {
+    let (key, nonce, mut buf) = (vec![0; 32], vec![0; 24], vec![0; 64]);
+    
+    /// Asserts that `$result` failed with `ApiMisuse($misuse)`
+    macro_rules! assert_misuse {
+        ($result:expr, $misuse:expr) => ({
+            let error = $result.error_or("The operation must fail");
+            let error = error.downcast_ref::<ChachaPolyError>().unwrap();
+            assert_eq!(error, &ChachaPolyError::ApiMisuse($misuse));
+        });
+    }
+    
+    // Claimed lengths beyond the limit
+    assert_misuse!(
+        XChachaPoly::aead_cipher().seal(&mut buf, usize::MAX, &[], &key, &nonce),
+        Misuse::TooMuchData
+    );
+    assert_misuse!(
+        XChachaPoly::aead_cipher().open(&mut buf, usize::MAX, &[], &key, &nonce),
+        Misuse::TooMuchData
+    );
+    
+    // Claimed lengths within the limit but way beyond the buffer size
+    assert_misuse!(
+        XChachaPoly::aead_cipher().seal(&mut buf, 1 << 30, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: (1 << 30) + 16, got: 64 }
+    );
+    assert_misuse!(
+        XChachaPoly::aead_cipher().open(&mut buf, 1 << 30, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: 1 << 30, got: 64 }
+    );
+    
+    // Buffers that can hold the plaintext but not the entire ciphertext
+    for missing in 1..=16 {
+        assert_misuse!(
+            XChachaPoly::aead_cipher().open(&mut buf[..64 - missing], 64, &[], &key, &nonce),
+            Misuse::BufferTooSmall{ needed: 64, got: 64 - missing }
+        );
+    }
+    
+    // A buffer that cannot even hold the tag
+    assert_misuse!(
+        XChachaPoly::aead_cipher().seal(&mut buf[..15], 0, &[], &key, &nonce),
+        Misuse::BufferTooSmall{ needed: 16, got: 15 }
+    );
+}
+
+#[test]
+fn test_open_buffer_one_byte_too_small() {
+    let (key, nonce) = (vec![0; 32], vec![0; 24]);
+    for &len in [16, 17, 31, 32, 33, 80, 81].iter() {
+        // Create a buffer that can hold the plaintext and all but one byte of the tag
+        let mut buf = vec![0; len - 1];
+        let expected = ChachaPolyError::ApiMisuse(Misuse::BufferTooSmall{
+            needed: len, got: len - 1
+        });
+        
+        // Opening in place must fail cleanly instead of panicking
+        let error = XChachaPoly.open(&mut buf, len, &[], &key, &nonce)
+            .error_or("The operation must fail");
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&expected));
+    }
+}
+
+
+#[test]
+fn test_oversized_buffer() {
+    let (key, nonce, plaintext) = (vec![0x07; 32], vec![0x0e; 24], vec![0x17; 100]);
+    let mut ciphertext = vec![0; plaintext.len() + 16];
+    XChachaPoly.seal_to(&mut ciphertext, &plaintext, &[], &key, &nonce).unwrap();
+    
+    // Open into an oversized buffer; only the plaintext must be written
+    let mut buf = vec![0xAA; plaintext.len() + 64];
+    let len = XChachaPoly.open_to(&mut buf, &ciphertext, &[], &key, &nonce).unwrap();
+    assert_eq!(&buf[..len], plaintext.as_slice());
+    assert!(buf[len..].iter().all(|b| *b == 0xAA));
+    
+    // A failed open must erase the written bytes and must not touch the tail
+    ciphertext[0] ^= 0x01;
+    let mut buf = vec![0xAA; plaintext.len() + 64];
+    XChachaPoly.open_to(&mut buf, &ciphertext, &[], &key, &nonce).unwrap_err();
+    assert!(buf[..plaintext.len()].iter().all(|b| *b == 0x00));
+    assert!(buf[plaintext.len()..].iter().all(|b| *b == 0xAA));
+}
+
+#[test]
+fn test_arr() {
+    let (key, nonce) = ([0x17; 32], [0x2a; 24]);
+    for &len in [0, 1, 15, 16, 17, 64, 300].iter() {
+        // Sealing must match the slice variant
+        let plaintext = vec![0x07; len];
+        let (mut sealed, mut sealed_arr) = (vec![0; len + 16], vec![0; len + 17]);
+        XChachaPoly.seal_to(&mut sealed, &plaintext, b"AD", &key, &nonce).unwrap();
+        let sealed_len = XChachaPoly.seal_to_arr(&mut sealed_arr, &plaintext, b"AD", &key, &nonce)
+            .unwrap();
+        assert_eq!(sealed, &sealed_arr[..sealed_len], "Length: {}", len);
+        
+        // Opening must match the slice variant
+        let mut buf = vec![0; len];
+        assert_eq!(XChachaPoly.open_to_arr(&mut buf, &sealed, b"AD", &key, &nonce), Ok(len));
+        assert_eq!(buf, plaintext, "Length: {}", len);
+    }
+    
+    // The errors must match the slice variants
+    let mut sealed = vec![0; 32];
+    XChachaPoly.seal_to(&mut sealed, &[0; 16], b"AD", &key, &nonce).unwrap();
+    let cases: &[(usize, &[u8], &[u8], bool)] = &[
+        (31, &[0; 16], b"", true), (15, &sealed, b"AD", false),
+        (16, &sealed[..15], b"AD", false), (16, &sealed, b"Ad", false)
+    ];
+    for &(buf_len, data, ad, seal) in cases.iter() {
+        let mut buf = vec![0; buf_len];
+        let (error, error_arr) = match seal {
+            true => (
+                XChachaPoly.seal_to(&mut buf, data, ad, &key, &nonce).unwrap_err(),
+                XChachaPoly.seal_to_arr(&mut buf, data, ad, &key, &nonce).unwrap_err()
+            ),
+            false => (
+                XChachaPoly.open_to(&mut buf, data, ad, &key, &nonce).unwrap_err(),
+                XChachaPoly.open_to_arr(&mut buf, data, ad, &key, &nonce).unwrap_err()
+            )
+        };
+        assert_eq!(error.downcast_ref::<ChachaPolyError>(), Some(&error_arr));
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "zeroize")]
+
+use crypto_api_chachapoly::{
+    ChaCha20, ChachaPolyWriter, ChachaPolyReader, SecretKey, XChaCha20Session,
+    crypto_api::rng::SecureRng, primitives::Poly1305State
+};
+use std::{ error::Error, io::{ Read, Write } };
+use zeroize::Zeroize;
+
+
+/// A deterministic (and thus insecure!) RNG that produces a different byte sequence on each call
+struct CounterRng(u8);
+impl SecureRng for CounterRng {
+    fn random(&mut self, buf: &mut[u8]) -> Result<(), Box<dyn Error + 'static>> {
+        buf.iter_mut().for_each(|b| { *b = self.0; self.0 = self.0.wrapping_add(1) });
+        Ok(())
+    }
+}
+
+
+#[test]
+fn test_poly1305_state() {
+    let mut state = Poly1305State::new(&[0x42; 32]);
+    state.update(b"Some data", true);
+    
+    // An erased state has `r = s = 0` and thus produces the all-zero tag
+    state.zeroize();
+    state.update(b"More data", true);
+    assert_eq!(state.finish(), [0; 16]);
+}
+
+
+#[test]
+fn test_secret_key() {
+    let mut key = SecretKey::generate(&mut CounterRng(1)).unwrap();
+    assert!(key.iter().all(|b| *b != 0));
+    
+    // An erased key is all-zero
+    key.zeroize();
+    assert_eq!(&key[..], &[0; 32][..]);
+}
+
+
+#[test]
+fn test_xchacha20_session() {
+    let mut session = XChaCha20Session::new(&[0x42; 32], &[0x2a; 16]).unwrap();
+    let (nonce_suffix, mut data) = ([0x17; 8], [0; 64]);
+    session.xor(&nonce_suffix, 0, &mut data);
+    
+    // An erased session uses the all-zero subkey
+    let mut expected = [0; 64];
+    ChaCha20::xor(&[0; 32], &nonce_suffix, 0, &mut expected);
+    assert_ne!(data, expected);
+    
+    session.zeroize();
+    let mut data = [0; 64];
+    session.xor(&nonce_suffix, 0, &mut data);
+    assert_eq!(data, expected);
+}
+
+
+#[test]
+fn test_stream() {
+    // Dropping the wiped stream state must not interfere with the stream
+    let (key, nonce_prefix) = ([0x17; 32], [0x2a; 7]);
+    let mut writer = ChachaPolyWriter::new(Vec::new(), &key, &nonce_prefix).unwrap();
+    writer.write_all(b"Streamed data").unwrap();
+    let sealed = writer.finish().unwrap();
+    
+    let mut reader = ChachaPolyReader::new(sealed.as_slice(), &key, &nonce_prefix).unwrap();
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).unwrap();
+    assert_eq!(plaintext, b"Streamed data");
+    assert!(reader.into_inner().is_empty());
+}
\ No newline at end of file